@@ -1,12 +1,10 @@
+use std::path::{Path, PathBuf};
 use std::process::Command;
-use std::path::Path;
 
 fn run_npm_command(args: &[&str], current_dir: &Path) -> std::io::Result<std::process::Output> {
     if cfg!(target_os = "windows") {
-        let mut cmd_args = vec!["cmd", "/C", "npm"];
-        cmd_args.extend_from_slice(args);
         Command::new("cmd")
-            .args(&["/C", "npm"])
+            .args(["/C", "npm"])
             .args(args)
             .current_dir(current_dir)
             .output()
@@ -18,33 +16,71 @@ fn run_npm_command(args: &[&str], current_dir: &Path) -> std::io::Result<std::pr
     }
 }
 
-fn main() {
-    println!("cargo:rerun-if-changed=ui/src");
-    println!("cargo:rerun-if-changed=ui/package.json");
-    println!("cargo:rerun-if-changed=ui/index.html");
-
-    let ui_dir = Path::new("ui");
-
+fn build_ui(ui_dir: &Path) -> Result<(), String> {
     if !ui_dir.exists() {
-        panic!("UI directory does not exist: {:?}", ui_dir);
+        return Err(format!("UI directory does not exist: {:?}", ui_dir));
+    }
+
+    if run_npm_command(&["--version"], ui_dir).is_err() {
+        return Err("npm is not installed".to_string());
     }
 
     if !ui_dir.join("node_modules").exists() {
         println!("cargo:warning=Installing ui dependencies...");
         let output = run_npm_command(&["install"], ui_dir)
-            .expect("Failed to run npm install");
+            .map_err(|e| format!("Failed to run npm install: {}", e))?;
         if !output.status.success() {
-            panic!("npm install failed: {}", String::from_utf8_lossy(&output.stderr));
+            return Err(format!("npm install failed: {}", String::from_utf8_lossy(&output.stderr)));
         }
     }
 
     println!("cargo:warning=Building ui...");
     let output = run_npm_command(&["run", "build"], ui_dir)
-        .expect("Failed to run npm run build");
-
+        .map_err(|e| format!("Failed to run npm run build: {}", e))?;
     if !output.status.success() {
-        panic!("npm run build failed: {}", String::from_utf8_lossy(&output.stderr));
+        return Err(format!("npm run build failed: {}", String::from_utf8_lossy(&output.stderr)));
+    }
+
+    Ok(())
+}
+
+fn main() {
+    println!("cargo::rustc-check-cfg=cfg(dpkv_no_ui)");
+    println!("cargo:rerun-if-env-changed=DPKV_UI_DIST");
+
+    // Parser-only and API-only builds never need Node.
+    if std::env::var_os("CARGO_FEATURE_UI").is_none() {
+        return;
     }
 
-    println!("cargo:warning=UI build completed successfully.");
-}
\ No newline at end of file
+    println!("cargo:rerun-if-changed=ui/src");
+    println!("cargo:rerun-if-changed=ui/package.json");
+    println!("cargo:rerun-if-changed=ui/index.html");
+
+    let manifest_dir = PathBuf::from(std::env::var("CARGO_MANIFEST_DIR").unwrap());
+
+    let prebuilt = std::env::var_os("DPKV_UI_DIST").filter(|p| !p.is_empty());
+    let dist_dir = if let Some(prebuilt) = prebuilt {
+        println!("cargo:warning=Using prebuilt UI from {:?}", prebuilt);
+        manifest_dir.join(prebuilt)
+    } else {
+        let ui_dir = manifest_dir.join("ui");
+        let dist_dir = ui_dir.join("dist");
+        match build_ui(&ui_dir) {
+            Ok(()) => println!("cargo:warning=UI build completed successfully."),
+            Err(e) if dist_dir.join("index.html").exists() => {
+                println!("cargo:warning={}", e);
+                println!("cargo:warning=Falling back to the existing bundle in {:?}", dist_dir);
+            }
+            Err(e) => println!("cargo:warning={}", e),
+        }
+        dist_dir
+    };
+
+    if dist_dir.join("index.html").exists() {
+        println!("cargo:rustc-env=DPKV_UI_DIST={}", dist_dir.display());
+    } else {
+        println!("cargo:warning=No UI bundle available, the server will run in API-only mode.");
+        println!("cargo:rustc-cfg=dpkv_no_ui");
+    }
+}
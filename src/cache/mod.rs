@@ -0,0 +1,469 @@
+mod schema;
+
+use anyhow::Result;
+use rusqlite::{Connection, OptionalExtension, params};
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+use std::fs::File;
+use std::io::{BufReader, Read};
+use std::path::{Path, PathBuf};
+use std::time::UNIX_EPOCH;
+use zip::ZipArchive;
+
+use crate::models::{AnalyticsEvent, Channel, ExtractedData, ParsedChannel, ParsedMessage};
+use crate::parser::{Parser, ParserOptions};
+
+/// On-disk SQLite cache of parsed packages, keyed by the SHA-256 of the package zip. A package
+/// is parsed again when it was cached by another version or with other [`ParserOptions`].
+pub struct PackageCache {
+    conn: Connection,
+}
+
+/// Summary row for a package stored in the cache.
+#[derive(Debug, Clone, Serialize)]
+pub struct CachedPackage {
+    pub hash: String,
+    pub source: String,
+    pub user_id: Option<String>,
+    pub username: Option<String>,
+    pub parsed_at: String,
+    pub channel_count: usize,
+    pub message_count: usize,
+}
+
+impl PackageCache {
+    pub fn open(path: &Path) -> Result<Self> {
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        let conn = Connection::open(path)?;
+        conn.pragma_update(None, "foreign_keys", true)?;
+
+        let version: i64 = conn.pragma_query_value(None, "user_version", |row| row.get(0))?;
+        if version != schema::CACHE_VERSION {
+            eprintln!(
+                "[debug] Cache version {} does not match {}, resetting cache",
+                version,
+                schema::CACHE_VERSION
+            );
+            conn.execute_batch(schema::DROP_TABLES)?;
+            conn.pragma_update(None, "user_version", schema::CACHE_VERSION)?;
+        }
+        conn.execute_batch(schema::CREATE_TABLES)?;
+
+        Ok(Self { conn })
+    }
+
+    /// `$DPKV_CACHE` if set, otherwise `~/.dpkv/cache.sqlite3`.
+    pub fn default_path() -> PathBuf {
+        if let Ok(path) = std::env::var("DPKV_CACHE") {
+            return PathBuf::from(path);
+        }
+        std::env::var_os("HOME")
+            .or_else(|| std::env::var_os("USERPROFILE"))
+            .map(PathBuf::from)
+            .unwrap_or_default()
+            .join(".dpkv")
+            .join("cache.sqlite3")
+    }
+
+    pub fn hash_file(path: &Path) -> Result<String> {
+        let mut reader = BufReader::new(File::open(path)?);
+        let mut hasher = Sha256::new();
+        let mut buffer = [0u8; 64 * 1024];
+        loop {
+            let read = reader.read(&mut buffer)?;
+            if read == 0 {
+                break;
+            }
+            hasher.update(&buffer[..read]);
+        }
+        Ok(format!("{:x}", hasher.finalize()))
+    }
+
+    /// [`PackageCache::hash_file`], reused while the file keeps the path, size and modification
+    /// time it was hashed with.
+    fn package_hash(&self, path: &Path) -> Result<String> {
+        let metadata = std::fs::metadata(path)?;
+        let path = std::fs::canonicalize(path)?.to_string_lossy().to_string();
+        let size = metadata.len() as i64;
+        let modified = metadata.modified()?.duration_since(UNIX_EPOCH)?.as_nanos() as i64;
+
+        let known: Option<String> = self
+            .conn
+            .query_row(
+                "SELECT hash FROM package_files WHERE path = ?1 AND size = ?2 AND modified_ns = ?3",
+                params![path, size, modified],
+                |row| row.get(0),
+            )
+            .optional()?;
+        if let Some(hash) = known {
+            return Ok(hash);
+        }
+
+        let hash = PackageCache::hash_file(Path::new(&path))?;
+        self.conn.execute(
+            "INSERT OR REPLACE INTO package_files (path, size, modified_ns, hash)
+             VALUES (?1, ?2, ?3, ?4)",
+            params![path, size, modified, hash],
+        )?;
+        Ok(hash)
+    }
+
+    /// Returns the cached result for the package at `path`, parsing and storing it on a miss.
+    pub fn extract<F>(
+        &mut self,
+        parser: &mut Parser,
+        path: &Path,
+        progress_callback: F,
+    ) -> Result<ExtractedData>
+    where
+        F: Fn(String) + Send + Sync,
+    {
+        progress_callback("Hashing package...".to_string());
+        let hash = self.package_hash(path)?;
+
+        let options = parser.options().fingerprint();
+        if let Some(data) = self.load_matching(&hash, Some(&options))? {
+            eprintln!("[debug] Loaded package {} from cache", hash);
+            progress_callback("Loaded from cache".to_string());
+            return Ok(data);
+        }
+
+        let archive = ZipArchive::new(BufReader::new(File::open(path)?))?;
+        let data = parser.parse_archive(archive, &progress_callback)?;
+
+        progress_callback("Saving to cache...".to_string());
+        let source = path
+            .file_name()
+            .map(|name| name.to_string_lossy().to_string())
+            .unwrap_or_default();
+        self.store(&hash, &source, parser.options(), &data)?;
+
+        Ok(data)
+    }
+
+    /// The cached result for a package, whatever options it was parsed with. Results from
+    /// another version are treated as missing.
+    pub fn load(&self, hash: &str) -> Result<Option<ExtractedData>> {
+        self.load_matching(hash, None)
+    }
+
+    fn load_matching(&self, hash: &str, options: Option<&str>) -> Result<Option<ExtractedData>> {
+        let row: Option<(String, i64, String, Option<Vec<u8>>)> = self
+            .conn
+            .query_row(
+                "SELECT stats, stats_version, options, avatar FROM packages WHERE hash = ?1",
+                params![hash],
+                |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?, row.get(3)?)),
+            )
+            .optional()?;
+        let Some((stats, stats_version, stored_options, avatar)) = row else {
+            return Ok(None);
+        };
+        if stats_version != schema::STATS_VERSION {
+            eprintln!(
+                "[debug] Package {} was cached by version {}, parsing again",
+                hash, stats_version
+            );
+            return Ok(None);
+        }
+        if options.is_some_and(|options| options != stored_options) {
+            eprintln!("[debug] Package {} was cached with other options, parsing again", hash);
+            return Ok(None);
+        }
+
+        let mut data: ExtractedData = match serde_json::from_str(&stats) {
+            Ok(data) => data,
+            Err(e) => {
+                eprintln!("[debug] Cached stats for {} are unreadable, parsing again: {}", hash, e);
+                return Ok(None);
+            }
+        };
+        if let Some(user) = data.user.as_mut() {
+            user.avatar = avatar;
+        }
+
+        let mut channel_stmt = self
+            .conn
+            .prepare("SELECT id, name, data FROM channels WHERE package_hash = ?1 ORDER BY rowid")?;
+        let mut message_stmt = self.conn.prepare(
+            "SELECT data FROM messages WHERE package_hash = ?1 AND channel_id = ?2 ORDER BY timestamp",
+        )?;
+
+        let rows = channel_stmt.query_map(params![hash], |row| {
+            Ok((
+                row.get::<_, String>(0)?,
+                row.get::<_, String>(1)?,
+                row.get::<_, String>(2)?,
+            ))
+        })?;
+
+        for row in rows {
+            let (id, name, channel_data) = row?;
+            let channel: Channel = serde_json::from_str(&channel_data)?;
+            let (messages, attachment_only) = message_stmt
+                .query_map(params![hash, id], |row| row.get::<_, String>(0))?
+                .map(|data| Ok(serde_json::from_str::<ParsedMessage>(&data?)?))
+                .collect::<Result<Vec<_>>>()?
+                .into_iter()
+                .partition(|message| !message.contents.is_empty());
+            data.channels.push(ParsedChannel {
+                channel,
+                name,
+                messages,
+                attachment_only,
+            });
+        }
+
+        let mut event_stmt = self.conn.prepare(
+            "SELECT event_type, timestamp, channel_id, guild_id FROM voice_events
+             WHERE package_hash = ?1 ORDER BY rowid",
+        )?;
+        data.voice_events = event_stmt
+            .query_map(params![hash], |row| {
+                Ok(AnalyticsEvent {
+                    event_type: row.get(0)?,
+                    timestamp: row.get(1)?,
+                    channel_id: row.get(2)?,
+                    guild_id: row.get(3)?,
+                })
+            })?
+            .collect::<rusqlite::Result<Vec<_>>>()?;
+
+        Ok(Some(data))
+    }
+
+    pub fn store(
+        &mut self,
+        hash: &str,
+        source: &str,
+        options: &ParserOptions,
+        data: &ExtractedData,
+    ) -> Result<()> {
+        let tx = self.conn.transaction()?;
+        tx.execute("DELETE FROM packages WHERE hash = ?1", params![hash])?;
+        tx.execute(
+            "INSERT INTO packages (hash, source, user_id, username, parsed_at, channel_count, message_count, stats, stats_version, options, avatar)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11)",
+            params![
+                hash,
+                source,
+                data.user.as_ref().map(|u| u.id.as_str()),
+                data.user.as_ref().map(|u| u.username.as_str()),
+                chrono::Utc::now().to_rfc3339(),
+                data.channels.len() as i64,
+                data.channels.iter().map(|c| c.messages.len()).sum::<usize>() as i64,
+                serde_json::to_string(data)?,
+                schema::STATS_VERSION,
+                options.fingerprint(),
+                data.user.as_ref().and_then(|u| u.avatar.as_deref()),
+            ],
+        )?;
+
+        {
+            let mut channel_stmt = tx.prepare(
+                "INSERT OR REPLACE INTO channels (package_hash, id, name, data) VALUES (?1, ?2, ?3, ?4)",
+            )?;
+            let mut message_stmt = tx.prepare(
+                "INSERT OR REPLACE INTO messages (package_hash, channel_id, id, timestamp, data)
+                 VALUES (?1, ?2, ?3, ?4, ?5)",
+            )?;
+            for parsed in &data.channels {
+                channel_stmt.execute(params![
+                    hash,
+                    parsed.channel.id,
+                    parsed.name,
+                    serde_json::to_string(&parsed.channel)?,
+                ])?;
+                for message in parsed.messages.iter().chain(&parsed.attachment_only) {
+                    message_stmt.execute(params![
+                        hash,
+                        parsed.channel.id,
+                        message.id as i64,
+                        message.timestamp,
+                        serde_json::to_string(message)?,
+                    ])?;
+                }
+            }
+        }
+
+        {
+            let mut event_stmt = tx.prepare(
+                "INSERT INTO voice_events (package_hash, event_type, timestamp, channel_id, guild_id)
+                 VALUES (?1, ?2, ?3, ?4, ?5)",
+            )?;
+            for event in &data.voice_events {
+                event_stmt.execute(params![
+                    hash,
+                    event.event_type,
+                    event.timestamp,
+                    event.channel_id,
+                    event.guild_id,
+                ])?;
+            }
+        }
+
+        tx.commit()?;
+        eprintln!("[debug] Stored package {} in cache", hash);
+        Ok(())
+    }
+
+    pub fn list(&self) -> Result<Vec<CachedPackage>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT hash, source, user_id, username, parsed_at, channel_count, message_count
+             FROM packages ORDER BY parsed_at DESC",
+        )?;
+        let packages = stmt
+            .query_map([], |row| {
+                Ok(CachedPackage {
+                    hash: row.get(0)?,
+                    source: row.get(1)?,
+                    user_id: row.get(2)?,
+                    username: row.get(3)?,
+                    parsed_at: row.get(4)?,
+                    channel_count: row.get::<_, i64>(5)? as usize,
+                    message_count: row.get::<_, i64>(6)? as usize,
+                })
+            })?
+            .collect::<rusqlite::Result<Vec<_>>>()?;
+        Ok(packages)
+    }
+
+    pub fn remove(&self, hash: &str) -> Result<bool> {
+        Ok(self.conn.execute("DELETE FROM packages WHERE hash = ?1", params![hash])? > 0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::fixtures::{USER_ID, data, guild_channel, package, temp_path, user};
+
+    fn cached() -> ExtractedData {
+        let mut data = data(vec![guild_channel(
+            "800000000000000001",
+            "general",
+            &[("2023-01-02T10:00:00Z", "second"), ("2023-01-01T10:00:00Z", "first")],
+        )]);
+        data.user = Some(user(serde_json::json!({})));
+        data.voice_events = vec![AnalyticsEvent {
+            event_type: "join_call".to_string(),
+            timestamp: "2023-01-01T10:00:00Z".to_string(),
+            channel_id: Some("1".to_string()),
+            guild_id: None,
+        }];
+        data
+    }
+
+    #[test]
+    fn round_trips_a_package() {
+        let mut cache = PackageCache::open(Path::new(":memory:")).unwrap();
+        cache.store("abc", "package.zip", &ParserOptions::default(), &cached()).unwrap();
+
+        let data = cache.load("abc").unwrap().unwrap();
+        assert_eq!(data.user.unwrap().id, USER_ID);
+        let contents: Vec<&str> =
+            data.channels[0].messages.iter().map(|m| m.contents.as_str()).collect();
+        assert_eq!(contents, ["first", "second"]);
+        assert_eq!(data.voice_events.len(), 1);
+
+        let list = cache.list().unwrap();
+        assert_eq!((list[0].source.as_str(), list[0].message_count), ("package.zip", 2));
+        assert!(cache.remove("abc").unwrap());
+        assert!(cache.load("abc").unwrap().is_none());
+    }
+
+    #[test]
+    fn misses_on_other_options_or_versions() {
+        let mut cache = PackageCache::open(Path::new(":memory:")).unwrap();
+        let options = ParserOptions::default();
+        cache.store("abc", "package.zip", &options, &cached()).unwrap();
+
+        let other = ParserOptions::default().top_count(3);
+        assert_ne!(options.fingerprint(), other.fingerprint());
+        assert!(cache.load_matching("abc", Some(&options.fingerprint())).unwrap().is_some());
+        assert!(cache.load_matching("abc", Some(&other.fingerprint())).unwrap().is_none());
+
+        cache
+            .conn
+            .execute("UPDATE packages SET stats_version = stats_version - 1", [])
+            .unwrap();
+        assert!(cache.load("abc").unwrap().is_none());
+
+        cache.store("abc", "package.zip", &options, &cached()).unwrap();
+        cache
+            .conn
+            .execute("UPDATE packages SET stats = '{\"old\": true}'", [])
+            .unwrap();
+        assert!(cache.load("abc").unwrap().is_none());
+    }
+
+    #[test]
+    fn extract_parses_again_with_other_options() {
+        let user = serde_json::json!({"id": USER_ID, "username": "tester", "discriminator": 0});
+        let channel = serde_json::json!({"id": "800000000000000001", "type": 1,
+            "recipients": [USER_ID, "222222222222222222"]});
+        let messages = serde_json::json!([{"ID": 1, "Timestamp": "2023-01-01T10:00:00+00:00",
+            "Contents": "hello", "Attachments": ""}]);
+        let archive = package(&[
+            ("Account/user.json", user.to_string()),
+            ("Messages/index.json", "{}".to_string()),
+            ("Messages/c800000000000000001/channel.json", channel.to_string()),
+            ("Messages/c800000000000000001/messages.json", messages.to_string()),
+        ]);
+        let path = temp_path("cache.zip");
+        std::fs::write(&path, archive.into_inner().into_inner()).unwrap();
+
+        let mut cache = PackageCache::open(Path::new(":memory:")).unwrap();
+        let hash = PackageCache::hash_file(&path).unwrap();
+        let first = cache.extract(&mut Parser::new(), &path, |_| {}).unwrap();
+        assert_eq!(first.channels.len(), 1);
+        let fingerprint = ParserOptions::default().fingerprint();
+        assert!(cache.load_matching(&hash, Some(&fingerprint)).unwrap().is_some());
+
+        let options = ParserOptions::default().top_count(3);
+        let mut parser = Parser::with_options(options.clone());
+        cache.extract(&mut parser, &path, |_| {}).unwrap();
+        assert!(cache.load_matching(&hash, Some(&fingerprint)).unwrap().is_none());
+        assert!(cache.load_matching(&hash, Some(&options.fingerprint())).unwrap().is_some());
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn unchanged_files_skip_hashing() {
+        let path = temp_path("hash.zip");
+        std::fs::write(&path, b"first").unwrap();
+        let cache = PackageCache::open(Path::new(":memory:")).unwrap();
+        let hash = cache.package_hash(&path).unwrap();
+        assert_eq!(hash, PackageCache::hash_file(&path).unwrap());
+
+        // A stored hash is trusted while size and modification time match.
+        cache.conn.execute("UPDATE package_files SET hash = 'stored'", []).unwrap();
+        assert_eq!(cache.package_hash(&path).unwrap(), "stored");
+
+        std::fs::write(&path, b"second, longer").unwrap();
+        assert_eq!(cache.package_hash(&path).unwrap(), PackageCache::hash_file(&path).unwrap());
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn stats_fields_match_the_version() {
+        fn fields(value: &serde_json::Value, prefix: &str, found: &mut Vec<String>) {
+            if let serde_json::Value::Object(map) = value {
+                for (key, value) in map {
+                    let path = format!("{}{}", prefix, key);
+                    fields(value, &format!("{}.", path), found);
+                    found.push(path);
+                }
+            }
+        }
+        let mut found = Vec::new();
+        fields(&serde_json::to_value(ExtractedData::default()).unwrap(), "", &mut found);
+        found.sort();
+        let fingerprint = format!("{:x}", Sha256::digest(found.join(",")));
+
+        // When this fails, bump STATS_VERSION and record the new fingerprint.
+        assert_eq!((schema::STATS_VERSION, &fingerprint[..16]), (3, "3f542d3b6fb008d5"));
+    }
+}
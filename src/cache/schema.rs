@@ -0,0 +1,63 @@
+/// Version of the table layout. Stats rows carry their own [`STATS_VERSION`].
+pub(super) const CACHE_VERSION: i64 = 1;
+
+/// Stored with every package's stats; rows from another version are parsed again. Bump it
+/// whenever the fields of `ExtractedData` or the way they are computed change.
+pub(super) const STATS_VERSION: i64 = 3;
+
+pub(super) const CREATE_TABLES: &str = "
+CREATE TABLE IF NOT EXISTS packages (
+    hash TEXT PRIMARY KEY,
+    source TEXT NOT NULL,
+    user_id TEXT,
+    username TEXT,
+    parsed_at TEXT NOT NULL,
+    channel_count INTEGER NOT NULL,
+    message_count INTEGER NOT NULL,
+    stats TEXT NOT NULL,
+    stats_version INTEGER NOT NULL,
+    options TEXT NOT NULL,             -- ParserOptions the stats were computed with
+    avatar BLOB
+);
+
+-- Lets a package that hasn't changed on disk skip hashing.
+CREATE TABLE IF NOT EXISTS package_files (
+    path TEXT PRIMARY KEY,             -- canonical path of the zip
+    size INTEGER NOT NULL,
+    modified_ns INTEGER NOT NULL,      -- modification time in nanoseconds since the epoch
+    hash TEXT NOT NULL
+);
+
+CREATE TABLE IF NOT EXISTS channels (
+    package_hash TEXT NOT NULL REFERENCES packages(hash) ON DELETE CASCADE,
+    id TEXT NOT NULL,
+    name TEXT NOT NULL,
+    data TEXT NOT NULL,
+    PRIMARY KEY (package_hash, id)
+);
+
+CREATE TABLE IF NOT EXISTS messages (
+    package_hash TEXT NOT NULL REFERENCES packages(hash) ON DELETE CASCADE,
+    channel_id TEXT NOT NULL,
+    id INTEGER NOT NULL,
+    timestamp TEXT NOT NULL,
+    data TEXT NOT NULL,
+    PRIMARY KEY (package_hash, channel_id, id)
+);
+CREATE TABLE IF NOT EXISTS voice_events (
+    package_hash TEXT NOT NULL REFERENCES packages(hash) ON DELETE CASCADE,
+    event_type TEXT NOT NULL,
+    timestamp TEXT NOT NULL,
+    channel_id TEXT,
+    guild_id TEXT
+);
+";
+
+pub(super) const DROP_TABLES: &str = "
+DROP TABLE IF EXISTS analytics_events;
+DROP TABLE IF EXISTS voice_events;
+DROP TABLE IF EXISTS messages;
+DROP TABLE IF EXISTS channels;
+DROP TABLE IF EXISTS packages;
+DROP TABLE IF EXISTS package_files;
+";
@@ -0,0 +1,112 @@
+use clap::{Parser as ClapParser, Subcommand, ValueEnum};
+use std::path::PathBuf;
+
+#[derive(ClapParser)]
+#[command(name = "dpkv", version, about = "Explore your Discord data package")]
+#[command(args_conflicts_with_subcommands = true)]
+pub struct Cli {
+    #[command(subcommand)]
+    pub command: Option<Command>,
+
+    /// Package to load before starting the viewer
+    pub package: Option<PathBuf>,
+
+    /// Let the viewer fall back to Discord's CDN for default avatars
+    #[arg(long)]
+    pub cdn_avatars: bool,
+
+    /// Pseudonymize DM partners, channel and server names, message contents and payment
+    /// descriptions in every output
+    #[arg(long, global = true)]
+    pub redact: bool,
+
+    /// File with words to hide from favorite words, one per line (implies --redact)
+    #[arg(long, global = true, value_name = "FILE")]
+    pub redact_words: Option<PathBuf>,
+
+    /// Currency to convert the total spent into, e.g. eur
+    #[arg(long, global = true, value_name = "CODE")]
+    pub currency: Option<String>,
+
+    /// CSV file with exchange rates to use on top of the bundled table, in its format or the
+    /// ECB's eurofxref-hist.csv
+    #[arg(long, global = true, value_name = "FILE")]
+    pub rates: Option<PathBuf>,
+
+    /// CSV file with word sentiment scores to use instead of the bundled lexicon
+    #[arg(long, global = true, value_name = "FILE")]
+    pub lexicon: Option<PathBuf>,
+
+    /// Parse packages without reading or writing the cache in ~/.dpkv
+    #[arg(long, global = true)]
+    pub no_cache: bool,
+}
+
+#[derive(Subcommand)]
+pub enum Command {
+    /// Start the web viewer
+    #[cfg(feature = "server")]
+    Serve {
+        /// Package to load before starting the viewer
+        package: Option<PathBuf>,
+        /// Let the viewer fall back to Discord's CDN for default avatars
+        #[arg(long)]
+        cdn_avatars: bool,
+    },
+    /// Export a package to another format
+    Export {
+        /// Package zip to export
+        package: PathBuf,
+        #[arg(long, value_enum, default_value_t = ExportFormat::Sqlite)]
+        format: ExportFormat,
+        /// Output file, or output directory for CSV
+        #[arg(short, long)]
+        output: PathBuf,
+    },
+    /// Render a standalone HTML report
+    Report {
+        /// Package zip to report on
+        package: PathBuf,
+        /// Output HTML file
+        #[arg(short, long, default_value = "report.html")]
+        output: PathBuf,
+    },
+    /// Compare an older and a newer package of the same account
+    Diff {
+        /// The older package zip
+        older: PathBuf,
+        /// The newer package zip
+        newer: PathBuf,
+        /// Print the differences as JSON
+        #[arg(long)]
+        json: bool,
+    },
+    /// Merge packages of the same account and report what each one contributed
+    Merge {
+        /// Package zips, in any order
+        #[arg(required = true, num_args = 2..)]
+        packages: Vec<PathBuf>,
+        /// Print the report as JSON
+        #[arg(long)]
+        json: bool,
+        /// Export the merged packages to this file, or directory for CSV
+        #[arg(short, long)]
+        output: Option<PathBuf>,
+        #[arg(long, value_enum, default_value_t = ExportFormat::Sqlite)]
+        format: ExportFormat,
+    },
+    /// Print a year-in-review summary
+    Wrapped {
+        /// Package zip to summarize
+        package: PathBuf,
+        /// Year to summarize, defaults to the latest year with messages
+        #[arg(long)]
+        year: Option<i32>,
+    },
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+pub enum ExportFormat {
+    Sqlite,
+    Csv,
+}
@@ -0,0 +1,383 @@
+//! Offline currency conversion for spending totals.
+//!
+//! A table of monthly exchange rates against the US dollar is bundled with the crate; each
+//! payment is converted at the rate of the month it was made in. Newer or more precise rates
+//! can be loaded from a local CSV file in the same format as `assets/exchange-rates.csv`.
+
+use anyhow::{Result, anyhow};
+use chrono::{DateTime, Datelike, NaiveDate, Utc};
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::path::Path;
+use std::sync::LazyLock;
+
+use crate::models::{ExtractedData, Payment, PaymentStatus};
+
+const BUNDLED_RATES: &str = include_str!("../assets/exchange-rates.csv");
+
+/// Rates in the table are expressed against this currency.
+pub const BASE_CURRENCY: &str = "usd";
+
+/// Monthly exchange rates, in units of each currency per [`BASE_CURRENCY`].
+#[derive(Debug, Clone, Default)]
+pub struct ExchangeRates {
+    rates: HashMap<String, BTreeMap<(i32, u32), f64>>,
+}
+
+impl ExchangeRates {
+    /// The rate table shipped with the crate, parsed on first use.
+    pub fn bundled() -> &'static Self {
+        static BUNDLED: LazyLock<ExchangeRates> = LazyLock::new(|| {
+            ExchangeRates::from_csv(BUNDLED_RATES).expect("bundled exchange rates are valid")
+        });
+        &BUNDLED
+    }
+
+    /// The bundled table with the months and currencies from the CSV file at `path` added on top.
+    /// The file is either in the bundled format or the ECB's `eurofxref-hist.csv`.
+    pub fn load(path: &Path) -> Result<Self> {
+        let content = std::fs::read_to_string(path)?;
+        let mut rates = Self::bundled().clone();
+        if content.trim_start().starts_with("Date,") {
+            rates.update(Self::from_ecb_history(&content)?);
+        } else {
+            rates.update(Self::from_csv(&content)?);
+        }
+        Ok(rates)
+    }
+
+    /// Monthly averages of the ECB's daily euro reference rates, as in `eurofxref-hist.csv`
+    /// from <https://www.ecb.europa.eu/stats/eurofxref/eurofxref-hist.zip>. Each day's rates
+    /// are turned into units per [`BASE_CURRENCY`] before averaging; `N/A` cells are skipped.
+    pub fn from_ecb_history(content: &str) -> Result<Self> {
+        let mut reader = csv::ReaderBuilder::new()
+            .trim(csv::Trim::All)
+            .flexible(true)
+            .from_reader(content.as_bytes());
+        let currencies: Vec<String> = reader.headers()?.iter().map(|c| c.to_lowercase()).collect();
+        let base = currencies
+            .iter()
+            .position(|c| c == BASE_CURRENCY)
+            .ok_or_else(|| anyhow!("No {} column in the ECB rates", BASE_CURRENCY))?;
+
+        // Sum and number of days per currency and month.
+        let mut sums: HashMap<(String, (i32, u32)), (f64, u32)> = HashMap::new();
+        for record in reader.records() {
+            let record = record?;
+            let day = record.get(0).unwrap_or_default();
+            let date = NaiveDate::parse_from_str(day, "%Y-%m-%d")
+                .map_err(|_| anyhow!("Invalid date {:?} in the ECB rates", day))?;
+            let Some(base_per_eur) = record.get(base).and_then(|v| v.parse::<f64>().ok()) else {
+                continue;
+            };
+            let key = (date.year(), date.month());
+            let mut add = |currency: &str, rate: f64| {
+                let sum = sums.entry((currency.to_string(), key)).or_default();
+                sum.0 += rate;
+                sum.1 += 1;
+            };
+            add("eur", 1.0 / base_per_eur);
+            for (currency, value) in currencies.iter().zip(record.iter()).skip(1) {
+                if currency.is_empty() || currency == BASE_CURRENCY {
+                    continue;
+                }
+                if let Ok(per_eur) = value.parse::<f64>() {
+                    add(currency, per_eur / base_per_eur);
+                }
+            }
+        }
+
+        let mut rates = Self::default();
+        for ((currency, month), (sum, days)) in sums {
+            rates.rates.entry(currency).or_default().insert(month, sum / f64::from(days));
+        }
+        Ok(rates)
+    }
+
+    /// Writes the months from `since` on in the format [`ExchangeRates::from_csv`] reads, one
+    /// column per currency in `currencies`. Rates are rounded to five significant digits.
+    pub fn to_csv(&self, currencies: &[&str], since: (i32, u32)) -> String {
+        let months: BTreeSet<(i32, u32)> = currencies
+            .iter()
+            .filter_map(|c| self.rates.get(*c))
+            .flat_map(|months| months.range(since..).map(|(month, _)| *month))
+            .collect();
+        let mut csv = format!("month,{}\n", currencies.join(","));
+        for (year, month) in months {
+            csv.push_str(&format!("{}-{:02}", year, month));
+            for currency in currencies {
+                csv.push(',');
+                if let Some(rate) = self.rates.get(*currency).and_then(|m| m.get(&(year, month))) {
+                    let decimals = (4 - rate.log10().floor() as i32).max(0) as usize;
+                    let rounded = format!("{:.*}", decimals, rate);
+                    if rounded.contains('.') {
+                        csv.push_str(rounded.trim_end_matches('0').trim_end_matches('.'));
+                    } else {
+                        csv.push_str(&rounded);
+                    }
+                }
+            }
+            csv.push('\n');
+        }
+        csv
+    }
+
+    /// Parses a table with a `month` column (`YYYY-MM`) followed by one column per currency.
+    /// Lines starting with `#` are comments and empty cells are skipped.
+    pub fn from_csv(content: &str) -> Result<Self> {
+        let mut reader = csv::ReaderBuilder::new()
+            .comment(Some(b'#'))
+            .trim(csv::Trim::All)
+            .from_reader(content.as_bytes());
+        let currencies: Vec<String> = reader
+            .headers()?
+            .iter()
+            .skip(1)
+            .map(|c| c.to_lowercase())
+            .collect();
+
+        let mut rates = Self::default();
+        for record in reader.records() {
+            let record = record?;
+            let month = record.get(0).unwrap_or_default();
+            let key = month
+                .split_once('-')
+                .and_then(|(year, month)| Some((year.parse().ok()?, month.parse().ok()?)))
+                .filter(|(_, month)| (1..=12).contains(month))
+                .ok_or_else(|| anyhow!("Invalid month {:?} in exchange rates", month))?;
+            for (currency, value) in currencies.iter().zip(record.iter().skip(1)) {
+                if value.is_empty() {
+                    continue;
+                }
+                let rate: f64 = value
+                    .parse()
+                    .map_err(|_| anyhow!("Invalid {} rate {:?} for {}", currency, value, month))?;
+                if rate <= 0.0 {
+                    return Err(anyhow!("Invalid {} rate {:?} for {}", currency, value, month));
+                }
+                rates.rates.entry(currency.clone()).or_default().insert(key, rate);
+            }
+        }
+        Ok(rates)
+    }
+
+    /// Adds every rate from `other`, replacing the months both tables have.
+    pub fn update(&mut self, other: ExchangeRates) {
+        for (currency, months) in other.rates {
+            self.rates.entry(currency).or_default().extend(months);
+        }
+    }
+
+    /// Whether `currency` can be converted at all.
+    pub fn supports(&self, currency: &str) -> bool {
+        let currency = currency.to_lowercase();
+        currency == BASE_CURRENCY || self.rates.contains_key(&currency)
+    }
+
+    /// Units of `currency` per [`BASE_CURRENCY`] at `date`. Dates outside the table use the
+    /// closest month available.
+    pub fn rate(&self, currency: &str, date: DateTime<Utc>) -> Option<f64> {
+        let currency = currency.to_lowercase();
+        if currency == BASE_CURRENCY {
+            return Some(1.0);
+        }
+        let months = self.rates.get(&currency)?;
+        months
+            .range(..=(date.year(), date.month()))
+            .next_back()
+            .or_else(|| months.iter().next())
+            .map(|(_, rate)| *rate)
+    }
+
+    /// Converts `amount` from one currency to another at the rates of `date`.
+    pub fn convert(&self, amount: f64, from: &str, to: &str, date: DateTime<Utc>) -> Option<f64> {
+        if from.eq_ignore_ascii_case(to) {
+            return Some(amount);
+        }
+        Some(amount / self.rate(from, date)? * self.rate(to, date)?)
+    }
+}
+
+/// Net spending converted into one currency.
+pub(crate) struct Spending {
+    pub currency: String,
+    pub total: f64,
+    pub by_year: BTreeMap<i32, f64>,
+    /// Currencies without a rate in the table, left out of the totals.
+    pub unconverted: Vec<String>,
+}
+
+/// Converts completed payments into `target`. Without a target, a single currency is kept
+/// as is and mixed currencies are converted into [`BASE_CURRENCY`].
+pub(crate) fn spending(
+    payments: &[Payment],
+    rates: &ExchangeRates,
+    target: Option<&str>,
+) -> Option<Spending> {
+    let completed: Vec<&Payment> = payments
+        .iter()
+        .filter(|p| p.status == PaymentStatus::Completed)
+        .collect();
+    if completed.is_empty() {
+        return None;
+    }
+
+    let currencies: BTreeSet<String> = completed.iter().map(|p| p.currency.to_lowercase()).collect();
+    let currency = match target {
+        Some(target) => target.to_lowercase(),
+        None if currencies.len() == 1 => currencies.into_iter().next().unwrap_or_default(),
+        None => BASE_CURRENCY.to_string(),
+    };
+
+    let mut spending = Spending {
+        currency,
+        total: 0.0,
+        by_year: BTreeMap::new(),
+        unconverted: Vec::new(),
+    };
+    for payment in completed {
+        let amount = payment.net_amount() as f64 / 100.0;
+        match rates.convert(amount, &payment.currency, &spending.currency, payment.created_at) {
+            Some(converted) => {
+                spending.total += converted;
+                *spending.by_year.entry(payment.created_at.year()).or_insert(0.0) += converted;
+            }
+            None => {
+                let currency = payment.currency.to_lowercase();
+                if !spending.unconverted.contains(&currency) {
+                    eprintln!(
+                        "[debug] No exchange rate for {}, leaving it out of the total",
+                        currency
+                    );
+                    spending.unconverted.push(currency);
+                }
+            }
+        }
+    }
+    Some(spending)
+}
+
+/// Recomputes `total_spent` and the yearly spending of `data` in the `target` currency.
+pub fn convert_spending(data: &mut ExtractedData, rates: &ExchangeRates, target: Option<&str>) {
+    let payments = data.user.as_ref().map(|u| u.payments.as_slice()).unwrap_or_default();
+    let spending = spending(payments, rates, target);
+    apply_spending(data, spending);
+}
+
+pub(crate) fn apply_spending(data: &mut ExtractedData, spending: Option<Spending>) {
+    match spending {
+        Some(spending) => {
+            data.total_spent = Some(spending.total);
+            data.spent_currency = Some(spending.currency);
+            data.payments.spent_by_year = spending.by_year;
+            data.payments.unconverted_currencies = spending.unconverted;
+        }
+        None => {
+            data.total_spent = None;
+            data.spent_currency = None;
+            data.payments.spent_by_year.clear();
+            data.payments.unconverted_currencies.clear();
+        }
+    }
+    for summary in &mut data.years {
+        summary.spent = data.payments.spent_by_year.get(&summary.year).copied();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn at(date: &str) -> DateTime<Utc> {
+        format!("{}T12:00:00Z", date).parse().unwrap()
+    }
+
+    fn rates() -> ExchangeRates {
+        ExchangeRates::from_csv("# test rates\nmonth,EUR,GBP\n2023-01,0.9,0.8\n2023-03,0.95,\n")
+            .unwrap()
+    }
+
+    #[test]
+    fn uses_the_closest_earlier_month() {
+        let rates = rates();
+        assert_eq!(rates.rate("usd", at("2020-01-01")), Some(1.0));
+        assert_eq!(rates.rate("EUR", at("2023-02-15")), Some(0.9));
+        assert_eq!(rates.rate("eur", at("2023-03-01")), Some(0.95));
+        assert_eq!(rates.rate("gbp", at("2024-01-01")), Some(0.8));
+        // Before the table starts, the first month is used.
+        assert_eq!(rates.rate("eur", at("2022-06-01")), Some(0.9));
+        assert_eq!(rates.rate("jpy", at("2023-01-01")), None);
+        assert_eq!(rates.convert(9.0, "eur", "usd", at("2023-01-10")), Some(10.0));
+        assert_eq!(rates.convert(5.0, "jpy", "JPY", at("2023-01-10")), Some(5.0));
+    }
+
+    #[test]
+    fn rejects_invalid_tables() {
+        assert!(ExchangeRates::from_csv("month,eur\n2023-13,0.9\n").is_err());
+        assert!(ExchangeRates::from_csv("month,eur\n2023-01,abc\n").is_err());
+        assert!(ExchangeRates::from_csv("month,eur\n2023-01,0\n").is_err());
+    }
+
+    #[test]
+    fn update_replaces_shared_months() {
+        let mut rates = rates();
+        rates.update(ExchangeRates::from_csv("month,eur,jpy\n2023-01,0.5,140\n").unwrap());
+        assert_eq!(rates.rate("eur", at("2023-01-01")), Some(0.5));
+        assert_eq!(rates.rate("eur", at("2023-03-01")), Some(0.95));
+        assert!(rates.supports("JPY"));
+    }
+
+    #[test]
+    fn averages_ecb_reference_rates() {
+        let ecb = "Date,USD,JPY,GBP,RUB,\n\
+                   2023-02-01,1.25,150,0.9,N/A,\n\
+                   2023-01-31,1.0,140,0.8,80,\n\
+                   2023-01-30,1.0,160,0.8,N/A,\n";
+        let rates = ExchangeRates::from_ecb_history(ecb).unwrap();
+        assert_eq!(rates.rate("eur", at("2023-01-15")), Some(1.0));
+        assert_eq!(rates.rate("eur", at("2023-02-15")), Some(0.8));
+        assert_eq!(rates.rate("jpy", at("2023-01-15")), Some(150.0));
+        assert_eq!(rates.rate("jpy", at("2023-02-15")), Some(120.0));
+        // Days without a rate are left out of the average.
+        assert_eq!(rates.rate("rub", at("2023-01-15")), Some(80.0));
+        assert_eq!(rates.rate("rub", at("2023-02-15")), Some(80.0));
+
+        let csv = rates.to_csv(&["eur", "gbp", "rub"], (2023, 1));
+        assert_eq!(csv, "month,eur,gbp,rub\n2023-01,1,0.8,80\n2023-02,0.8,0.72,\n");
+        let parsed = ExchangeRates::from_csv(&csv).unwrap();
+        assert_eq!(parsed.rate("gbp", at("2023-02-15")), Some(0.72));
+        assert!(ExchangeRates::from_ecb_history("Date,JPY\n2023-01-02,140\n").is_err());
+    }
+
+    #[test]
+    fn bundled_rates_are_parsed_once() {
+        assert!(std::ptr::eq(ExchangeRates::bundled(), ExchangeRates::bundled()));
+        assert!(ExchangeRates::bundled().supports("eur"));
+    }
+
+    #[test]
+    fn converts_mixed_currencies() {
+        let payment = |currency: &str, amount: i64, date: &str| -> Payment {
+            serde_json::from_value(json!({
+                "id": "1", "status": 1, "currency": currency, "amount": amount,
+                "created_at": format!("{}T12:00:00Z", date),
+            }))
+            .unwrap()
+        };
+        let payments = [
+            payment("usd", 1000, "2023-01-10"),
+            payment("eur", 900, "2023-01-10"),
+            payment("xyz", 500, "2024-01-10"),
+        ];
+
+        let spending = spending(&payments, &rates(), None).unwrap();
+        assert_eq!(spending.currency, "usd");
+        assert_eq!(spending.total, 20.0);
+        assert_eq!(spending.unconverted, ["xyz"]);
+        assert_eq!(spending.by_year.get(&2024), None);
+
+        let spending = super::spending(&payments[..1], &rates(), Some("EUR")).unwrap();
+        assert_eq!((spending.currency.as_str(), spending.total), ("eur", 9.0));
+        assert!(super::spending(&[], &rates(), None).is_none());
+    }
+}
@@ -0,0 +1,623 @@
+use anyhow::Result;
+use serde::Serialize;
+use std::path::Path;
+
+use crate::models::ExtractedData;
+
+#[derive(Serialize)]
+struct HourRow {
+    hour: usize,
+    message_count: usize,
+}
+
+#[derive(Serialize)]
+struct DayRow {
+    date: chrono::NaiveDate,
+    message_count: usize,
+}
+
+#[derive(Serialize)]
+struct TopChannelRow<'a> {
+    rank: usize,
+    name: &'a str,
+    guild_name: &'a str,
+    channel_type: &'a str,
+    created_at: Option<String>,
+    message_count: usize,
+}
+
+#[derive(Serialize)]
+struct TopGroupDMRow<'a> {
+    rank: usize,
+    channel_id: &'a str,
+    name: &'a str,
+    participants: String,
+    message_count: usize,
+}
+
+#[derive(Serialize)]
+struct ChannelTypeRow<'a> {
+    channel_type: &'a str,
+    channel_count: usize,
+    message_count: usize,
+}
+
+#[derive(Serialize)]
+struct TopDMRow<'a> {
+    rank: usize,
+    channel_id: &'a str,
+    user_id: &'a str,
+    username: &'a str,
+    started_at: Option<String>,
+    message_count: usize,
+}
+
+#[derive(Serialize)]
+struct FavoriteWordRow<'a> {
+    rank: usize,
+    word: &'a str,
+    count: usize,
+}
+
+#[derive(Serialize)]
+struct PhraseRow<'a> {
+    words: usize,
+    rank: usize,
+    phrase: &'a str,
+    count: usize,
+}
+
+#[derive(Serialize)]
+struct WordSpikeRow<'a> {
+    word: &'a str,
+    month: String,
+    count: usize,
+    baseline: String,
+}
+
+#[derive(Serialize)]
+struct LanguageRow<'a> {
+    code: &'a str,
+    name: &'a str,
+    message_count: usize,
+    share: String,
+}
+
+#[derive(Serialize)]
+struct LanguageWordRow<'a> {
+    language: &'a str,
+    rank: usize,
+    word: &'a str,
+    count: usize,
+}
+
+#[derive(Serialize)]
+struct ChannelLanguageRow<'a> {
+    channel_id: &'a str,
+    name: &'a str,
+    language: &'a str,
+    message_count: usize,
+    share: String,
+}
+
+#[derive(Serialize)]
+struct ChannelSentimentRow<'a> {
+    channel_id: &'a str,
+    name: &'a str,
+    channel_type: &'a str,
+    scored_message_count: usize,
+    average: String,
+}
+
+#[derive(Serialize)]
+struct DateSentimentRow<'a> {
+    kind: &'a str,
+    date: chrono::NaiveDate,
+    scored_message_count: usize,
+    average: String,
+}
+
+#[derive(Serialize)]
+struct MessageTypeRow<'a> {
+    kind: &'a str,
+    message_count: usize,
+    share: String,
+}
+
+#[derive(Serialize)]
+struct CodeLanguageRow<'a> {
+    language: &'a str,
+    block_count: usize,
+}
+
+#[derive(Serialize)]
+struct CommandRow<'a> {
+    rank: usize,
+    command: &'a str,
+    count: usize,
+}
+
+#[derive(Serialize)]
+struct LengthBucketRow {
+    min: usize,
+    max: Option<usize>,
+    message_count: usize,
+}
+
+#[derive(Serialize)]
+struct ChannelLengthRow<'a> {
+    channel_id: &'a str,
+    name: &'a str,
+    channel_type: &'a str,
+    message_count: usize,
+    average: String,
+    median: String,
+}
+
+#[derive(Serialize)]
+struct ChannelSessionsRow<'a> {
+    channel_id: &'a str,
+    name: &'a str,
+    session_count: usize,
+    median_session_minutes: String,
+    median_session_messages: String,
+}
+
+#[derive(Serialize)]
+struct VoiceTimeRow<'a> {
+    kind: &'a str,
+    id: &'a str,
+    name: Option<&'a str>,
+    session_count: usize,
+    minutes: String,
+}
+
+#[derive(Serialize)]
+struct VoiceHourRow {
+    hour: usize,
+    minutes: String,
+}
+
+#[derive(Serialize)]
+struct PaymentRow<'a> {
+    id: &'a str,
+    created_at: String,
+    currency: &'a str,
+    amount: String,
+    amount_refunded: String,
+    status: String,
+    sku_id: &'a str,
+    description: &'a str,
+}
+
+#[derive(Serialize)]
+struct YearlySpendingRow<'a> {
+    year: i32,
+    currency: &'a str,
+    amount: String,
+}
+
+#[derive(Serialize)]
+struct YearRow<'a> {
+    year: i32,
+    message_count: usize,
+    character_count: usize,
+    active_days: usize,
+    top_channel: &'a str,
+    top_dm: &'a str,
+    top_word: &'a str,
+    new_guild_count: usize,
+    spent: String,
+}
+
+#[derive(Serialize)]
+struct SubscriptionRow<'a> {
+    description: &'a str,
+    sku_id: &'a str,
+    start: String,
+    end: String,
+    payment_count: usize,
+}
+
+/// Writes one CSV file per statistic into `output_dir`.
+pub fn export_csv(data: &ExtractedData, output_dir: &Path) -> Result<()> {
+    std::fs::create_dir_all(output_dir)?;
+
+    write_rows(
+        &output_dir.join("hours.csv"),
+        data.hours_values
+            .iter()
+            .enumerate()
+            .map(|(hour, &message_count)| HourRow {
+                hour,
+                message_count,
+            }),
+    )?;
+
+    write_rows(
+        &output_dir.join("daily.csv"),
+        data.daily_values
+            .iter()
+            .map(|(&date, &message_count)| DayRow {
+                date,
+                message_count,
+            }),
+    )?;
+
+    write_rows(
+        &output_dir.join("top_channels.csv"),
+        data.top_channels
+            .iter()
+            .enumerate()
+            .map(|(index, channel)| TopChannelRow {
+                rank: index + 1,
+                name: &channel.name,
+                guild_name: channel.guild_name.as_deref().unwrap_or(""),
+                channel_type: channel.channel_type.name(),
+                created_at: channel.created_at.map(|dt| dt.to_rfc3339()),
+                message_count: channel.message_count,
+            }),
+    )?;
+
+    write_rows(
+        &output_dir.join("channel_types.csv"),
+        data.channel_types.iter().map(|stats| ChannelTypeRow {
+            channel_type: stats.channel_type.name(),
+            channel_count: stats.channel_count,
+            message_count: stats.message_count,
+        }),
+    )?;
+
+    write_rows(
+        &output_dir.join("top_dms.csv"),
+        data.top_dms.iter().enumerate().map(|(index, dm)| TopDMRow {
+            rank: index + 1,
+            channel_id: &dm.id,
+            user_id: &dm.dm_user_id,
+            username: dm
+                .user_data
+                .as_ref()
+                .map(|u| u.username.as_str())
+                .unwrap_or(""),
+            started_at: dm.started_at.map(|dt| dt.to_rfc3339()),
+            message_count: dm.message_count,
+        }),
+    )?;
+
+    write_rows(
+        &output_dir.join("top_group_dms.csv"),
+        data.top_group_dms
+            .iter()
+            .enumerate()
+            .map(|(index, group)| TopGroupDMRow {
+                rank: index + 1,
+                channel_id: &group.id,
+                name: &group.name,
+                participants: group
+                    .participants
+                    .iter()
+                    .map(|u| u.username.as_str())
+                    .collect::<Vec<_>>()
+                    .join(", "),
+                message_count: group.message_count,
+            }),
+    )?;
+
+    write_rows(
+        &output_dir.join("channel_sessions.csv"),
+        data.activity
+            .channel_sessions
+            .iter()
+            .map(|channel| ChannelSessionsRow {
+                channel_id: &channel.channel_id,
+                name: &channel.name,
+                session_count: channel.session_count,
+                median_session_minutes: format!("{:.1}", channel.median_session_minutes),
+                median_session_messages: format!("{:.1}", channel.median_session_messages),
+            }),
+    )?;
+
+    write_rows(
+        &output_dir.join("favorite_words.csv"),
+        data.favorite_words
+            .iter()
+            .enumerate()
+            .map(|(index, word)| FavoriteWordRow {
+                rank: index + 1,
+                word: &word.word,
+                count: word.count,
+            }),
+    )?;
+
+    let trends = &data.word_trends;
+    let phrases = [(2, &trends.bigrams), (3, &trends.trigrams)];
+    write_rows(
+        &output_dir.join("phrases.csv"),
+        phrases.into_iter().flat_map(|(words, phrases)| {
+            phrases
+                .iter()
+                .enumerate()
+                .map(move |(index, phrase)| PhraseRow {
+                    words,
+                    rank: index + 1,
+                    phrase: &phrase.phrase,
+                    count: phrase.count,
+                })
+        }),
+    )?;
+
+    let languages = &data.languages;
+    write_rows(
+        &output_dir.join("languages.csv"),
+        languages.languages.iter().map(|language| LanguageRow {
+            code: &language.code,
+            name: &language.name,
+            message_count: language.message_count,
+            share: format!("{:.4}", language.share),
+        }),
+    )?;
+
+    write_rows(
+        &output_dir.join("language_words.csv"),
+        languages.languages.iter().flat_map(|language| {
+            language
+                .favorite_words
+                .iter()
+                .enumerate()
+                .map(move |(index, word)| LanguageWordRow {
+                    language: &language.code,
+                    rank: index + 1,
+                    word: &word.word,
+                    count: word.count,
+                })
+        }),
+    )?;
+
+    write_rows(
+        &output_dir.join("channel_languages.csv"),
+        languages.channels.iter().flat_map(|channel| {
+            channel.languages.iter().map(move |language| ChannelLanguageRow {
+                channel_id: &channel.channel_id,
+                name: &channel.name,
+                language: &language.code,
+                message_count: language.message_count,
+                share: format!("{:.4}", language.share),
+            })
+        }),
+    )?;
+
+    let content = &data.content;
+    let kinds = [
+        ("question", &content.questions),
+        ("link", &content.links),
+        ("code", &content.code),
+        ("command", &content.commands),
+        ("to_bot", &content.replies_to_bots),
+        ("shouting", &content.shouting),
+    ];
+    write_rows(
+        &output_dir.join("message_types.csv"),
+        kinds.into_iter().map(|(kind, share)| MessageTypeRow {
+            kind,
+            message_count: share.message_count,
+            share: format!("{:.4}", share.share),
+        }),
+    )?;
+
+    write_rows(
+        &output_dir.join("code_languages.csv"),
+        content.code_languages.iter().map(|language| CodeLanguageRow {
+            language: &language.language,
+            block_count: language.block_count,
+        }),
+    )?;
+
+    write_rows(
+        &output_dir.join("commands.csv"),
+        content
+            .top_commands
+            .iter()
+            .enumerate()
+            .map(|(index, command)| CommandRow {
+                rank: index + 1,
+                command: &command.command,
+                count: command.count,
+            }),
+    )?;
+
+    let lengths = &data.lengths;
+    write_rows(
+        &output_dir.join("message_lengths.csv"),
+        lengths.histogram.iter().map(|bucket| LengthBucketRow {
+            min: bucket.min,
+            max: bucket.max,
+            message_count: bucket.message_count,
+        }),
+    )?;
+
+    write_rows(
+        &output_dir.join("channel_lengths.csv"),
+        lengths
+            .channels
+            .iter()
+            .chain(&lengths.dms)
+            .map(|channel| ChannelLengthRow {
+                channel_id: &channel.channel_id,
+                name: &channel.name,
+                channel_type: channel.channel_type.name(),
+                message_count: channel.message_count,
+                average: format!("{:.1}", channel.average),
+                median: format!("{:.1}", channel.median),
+            }),
+    )?;
+
+    let sentiment = &data.sentiment;
+    write_rows(
+        &output_dir.join("sentiment_channels.csv"),
+        sentiment
+            .channels
+            .iter()
+            .chain(&sentiment.dms)
+            .map(|channel| ChannelSentimentRow {
+                channel_id: &channel.channel_id,
+                name: &channel.name,
+                channel_type: channel.channel_type.name(),
+                scored_message_count: channel.scored_message_count,
+                average: format!("{:.4}", channel.average),
+            }),
+    )?;
+
+    let dated = [
+        ("month", &sentiment.months),
+        ("most_positive_day", &sentiment.most_positive_days),
+        ("most_negative_day", &sentiment.most_negative_days),
+    ];
+    write_rows(
+        &output_dir.join("sentiment_dates.csv"),
+        dated.into_iter().flat_map(|(kind, dates)| {
+            dates.iter().map(move |date| DateSentimentRow {
+                kind,
+                date: date.date,
+                scored_message_count: date.scored_message_count,
+                average: format!("{:.4}", date.average),
+            })
+        }),
+    )?;
+
+    write_rows(
+        &output_dir.join("word_spikes.csv"),
+        trends.spikes.iter().map(|spike| WordSpikeRow {
+            word: &spike.word,
+            month: spike.month.format("%Y-%m").to_string(),
+            count: spike.count,
+            baseline: format!("{:.2}", spike.baseline),
+        }),
+    )?;
+
+    let voice = &data.voice;
+    let voice_times = [("guild", &voice.guilds), ("channel", &voice.channels)];
+    write_rows(
+        &output_dir.join("voice_time.csv"),
+        voice_times.into_iter().flat_map(|(kind, times)| {
+            times.iter().map(move |time| VoiceTimeRow {
+                kind,
+                id: &time.id,
+                name: time.name.as_deref(),
+                session_count: time.session_count,
+                minutes: format!("{:.1}", time.minutes),
+            })
+        }),
+    )?;
+
+    write_rows(
+        &output_dir.join("voice_hours.csv"),
+        voice
+            .hours_minutes
+            .iter()
+            .enumerate()
+            .map(|(hour, minutes)| VoiceHourRow {
+                hour,
+                minutes: format!("{:.1}", minutes),
+            }),
+    )?;
+
+    write_rows(
+        &output_dir.join("payments.csv"),
+        data.user
+            .iter()
+            .flat_map(|user| user.payments.iter())
+            .map(|payment| PaymentRow {
+                id: &payment.id,
+                created_at: payment.created_at.to_rfc3339(),
+                currency: &payment.currency,
+                amount: format!("{:.2}", payment.amount as f64 / 100.0),
+                amount_refunded: format!("{:.2}", payment.amount_refunded as f64 / 100.0),
+                status: payment.status.to_string(),
+                sku_id: payment.sku_id.as_deref().unwrap_or(""),
+                description: &payment.description,
+            }),
+    )?;
+
+    let mut yearly: Vec<YearlySpendingRow> = Vec::new();
+    for (year, totals) in &data.payments.by_year {
+        let mut currencies: Vec<_> = totals.iter().collect();
+        currencies.sort_by(|a, b| a.0.cmp(b.0));
+        yearly.extend(currencies.into_iter().map(|(currency, amount)| YearlySpendingRow {
+            year: *year,
+            currency,
+            amount: format!("{:.2}", amount),
+        }));
+    }
+    write_rows(&output_dir.join("spending_by_year.csv"), yearly.into_iter())?;
+
+    write_rows(
+        &output_dir.join("subscriptions.csv"),
+        data.payments.subscriptions.iter().map(|period| SubscriptionRow {
+            description: &period.description,
+            sku_id: period.sku_id.as_deref().unwrap_or(""),
+            start: period.start.to_rfc3339(),
+            end: period.end.to_rfc3339(),
+            payment_count: period.payment_count,
+        }),
+    )?;
+
+    write_rows(
+        &output_dir.join("years.csv"),
+        data.years.iter().map(|summary| YearRow {
+            year: summary.year,
+            message_count: summary.message_count,
+            character_count: summary.character_count,
+            active_days: summary.active_days,
+            top_channel: summary.top_channels.first().map_or("", |c| c.name.as_str()),
+            top_dm: summary.top_dms.first().map_or("", |dm| {
+                dm.user_data
+                    .as_ref()
+                    .map_or(dm.dm_user_id.as_str(), |u| u.username.as_str())
+            }),
+            top_word: summary.favorite_words.first().map_or("", |w| w.word.as_str()),
+            new_guild_count: summary.new_guilds.len(),
+            spent: summary.spent.map(|s| format!("{:.2}", s)).unwrap_or_default(),
+        }),
+    )?;
+
+    eprintln!("[debug] Exported CSV files to {}", output_dir.display());
+    Ok(())
+}
+
+// The header row comes from the row's field names, so an empty table is an empty file.
+fn write_rows<T: Serialize>(path: &Path, rows: impl Iterator<Item = T>) -> Result<()> {
+    let mut writer = ::csv::WriterBuilder::new()
+        .has_headers(true)
+        .from_path(path)?;
+    for row in rows {
+        writer.serialize(row)?;
+    }
+    writer.flush()?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::fixtures::{compute_stats, data, guild_channel, temp_path};
+
+    #[test]
+    fn headers_follow_the_row_fields() {
+        let mut data = data(vec![guild_channel(
+            "1",
+            "general",
+            &[("2023-01-01T10:00:00Z", "is this right?"), ("2023-01-02T10:00:00Z", "yes")],
+        )]);
+        compute_stats(&mut data);
+        let dir = temp_path("csv");
+        export_csv(&data, &dir).unwrap();
+
+        let read = |name: &str| std::fs::read_to_string(dir.join(name)).unwrap();
+        assert_eq!(read("daily.csv"), "date,message_count\n2023-01-01,1\n2023-01-02,1\n");
+        assert_eq!(read("hours.csv").lines().count(), 25);
+        assert!(read("message_types.csv").contains("question,1,0.5"));
+        assert!(read("message_types.csv").starts_with("kind,message_count,share\n"));
+        assert_eq!(read("payments.csv"), "");
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
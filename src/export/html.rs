@@ -0,0 +1,654 @@
+use anyhow::Result;
+use std::fmt::Write;
+use std::path::Path;
+
+use crate::models::{ExtractedData, PaymentStatus};
+
+const STYLE: &str = "
+body { font-family: system-ui, sans-serif; background: #313338; color: #dbdee1; margin: 0; padding: 2rem; }
+main { max-width: 960px; margin: 0 auto; }
+header { display: flex; align-items: center; gap: 1rem; }
+header img { width: 96px; height: 96px; border-radius: 50%; }
+h1 { margin: 0; color: #f2f3f5; }
+h2 { color: #f2f3f5; border-bottom: 1px solid #4e5058; padding-bottom: .25rem; }
+section { background: #2b2d31; border-radius: 8px; padding: 1rem 1.5rem; margin: 1.5rem 0; }
+.stats { display: grid; grid-template-columns: repeat(auto-fill, minmax(180px, 1fr)); gap: 1rem; }
+.stat strong { display: block; font-size: 1.6rem; color: #f2f3f5; }
+.bar-row { display: grid; grid-template-columns: 14rem 1fr 5rem; gap: .5rem; align-items: center; margin: .25rem 0; }
+.bar-row span:first-child { overflow: hidden; text-overflow: ellipsis; white-space: nowrap; }
+.bar { background: #5865f2; height: 1rem; border-radius: 4px; }
+.count { text-align: right; font-variant-numeric: tabular-nums; }
+.hours { display: flex; align-items: flex-end; gap: 4px; height: 160px; }
+.hours div { flex: 1; display: flex; flex-direction: column; justify-content: flex-end; align-items: center; height: 100%; font-size: .7rem; }
+.hours .bar { display: block; width: 100%; }
+table { width: 100%; border-collapse: collapse; }
+td, th { text-align: left; padding: .25rem .5rem; border-bottom: 1px solid #3f4147; }
+footer { text-align: center; color: #949ba4; font-size: .8rem; }
+";
+
+/// Renders a standalone HTML report with inline charts and the avatar embedded.
+pub fn export_html(data: &ExtractedData, output: &Path) -> Result<()> {
+    std::fs::write(output, render_report(data)?)?;
+    eprintln!("[debug] Wrote HTML report to {}", output.display());
+    Ok(())
+}
+
+fn render_report(data: &ExtractedData) -> Result<String> {
+    let mut html = String::new();
+    let username = data
+        .user
+        .as_ref()
+        .map(|u| u.username.as_str())
+        .unwrap_or("Unknown user");
+
+    writeln!(html, "<!DOCTYPE html>")?;
+    writeln!(html, "<html lang=\"en\"><head><meta charset=\"utf-8\">")?;
+    writeln!(html, "<title>{} - Discord data package report</title>", escape_html(username))?;
+    writeln!(html, "<style>{}</style></head><body><main>", STYLE)?;
+
+    writeln!(html, "<header>")?;
+    if let Some(src) = avatar_src(data) {
+        writeln!(html, "<img src=\"{}\" alt=\"avatar\">", escape_html(&src))?;
+    }
+    writeln!(html, "<div><h1>{}</h1>", escape_html(username))?;
+    if let Some(user) = &data.user {
+        if !data.redacted {
+            writeln!(html, "<p>User ID {}</p>", escape_html(&user.id))?;
+        }
+        if let Some(created_at) = data.account_created_at {
+            writeln!(html, "<p>Member since {}</p>", created_at.format("%B %-d, %Y"))?;
+        }
+    }
+    if !data.linked_services.is_empty() {
+        writeln!(
+            html,
+            "<p>Linked to {}</p>",
+            escape_html(&data.linked_services.join(", "))
+        )?;
+    }
+    writeln!(html, "</div></header>")?;
+
+    writeln!(html, "<section><h2>Overview</h2><div class=\"stats\">")?;
+    for (label, value) in [
+        ("Messages", data.message_count),
+        ("Characters", data.character_count),
+        ("Channels", data.channel_count),
+        ("DMs", data.dm_channel_count),
+        ("Group DMs", data.group_dm_count),
+        ("Guilds", data.guild_count),
+        ("Friends", data.friend_count),
+        ("Blocked", data.blocked_count),
+    ] {
+        writeln!(
+            html,
+            "<div class=\"stat\"><strong>{}</strong>{}</div>",
+            value, label
+        )?;
+    }
+    for (label, value) in [
+        ("App opens", data.open_count),
+        ("Reactions added", data.add_reaction_count),
+        ("Voice channels joined", data.join_voice_channel_count),
+    ] {
+        if let Some(value) = value {
+            writeln!(
+                html,
+                "<div class=\"stat\"><strong>{}</strong>{}</div>",
+                value, label
+            )?;
+        }
+    }
+    writeln!(html, "</div></section>")?;
+
+    write_highlights(&mut html, data)?;
+    write_content_stats(&mut html, data)?;
+    write_length_stats(&mut html, data)?;
+
+    writeln!(html, "<section><h2>Messages by hour</h2><div class=\"hours\">")?;
+    let max_hour = data.hours_values.iter().copied().max().unwrap_or(0).max(1);
+    for (hour, &count) in data.hours_values.iter().enumerate() {
+        writeln!(
+            html,
+            "<div title=\"{} messages\"><span class=\"bar\" style=\"height:{:.1}%\"></span>{}</div>",
+            count,
+            count as f64 * 100.0 / max_hour as f64,
+            hour
+        )?;
+    }
+    writeln!(html, "</div></section>")?;
+
+    let channels: Vec<(String, usize)> = data
+        .top_channels
+        .iter()
+        .map(|c| match &c.guild_name {
+            Some(guild) => (format!("{} ({})", c.name, guild), c.message_count),
+            None => (c.name.clone(), c.message_count),
+        })
+        .collect();
+    write_bar_chart(&mut html, "Top channels", &channels)?;
+
+    let dms: Vec<(String, usize)> = data
+        .top_dms
+        .iter()
+        .map(|dm| {
+            let name = dm
+                .user_data
+                .as_ref()
+                .map(|u| u.username.clone())
+                .unwrap_or_else(|| dm.dm_user_id.clone());
+            (name, dm.message_count)
+        })
+        .collect();
+    write_bar_chart(&mut html, "Top DMs", &dms)?;
+
+    let groups: Vec<(String, usize)> = data
+        .top_group_dms
+        .iter()
+        .map(|group| {
+            let participants: Vec<&str> =
+                group.participants.iter().map(|u| u.username.as_str()).collect();
+            (
+                format!("{} ({})", group.name, participants.join(", ")),
+                group.message_count,
+            )
+        })
+        .collect();
+    write_bar_chart(&mut html, "Top group DMs", &groups)?;
+
+    let words: Vec<(String, usize)> = data
+        .favorite_words
+        .iter()
+        .map(|w| (w.word.clone(), w.count))
+        .collect();
+    write_bar_chart(&mut html, "Favorite words", &words)?;
+
+    let phrases: Vec<(String, usize)> = data
+        .word_trends
+        .bigrams
+        .iter()
+        .chain(&data.word_trends.trigrams)
+        .map(|p| (p.phrase.clone(), p.count))
+        .collect();
+    write_bar_chart(&mut html, "Favorite phrases", &phrases)?;
+
+    let languages: Vec<(String, usize)> = data
+        .languages
+        .languages
+        .iter()
+        .map(|l| (format!("{} ({:.0}%)", l.name, l.share * 100.0), l.message_count))
+        .collect();
+    write_bar_chart(&mut html, "Languages", &languages)?;
+
+    write_sentiment(&mut html, data)?;
+    write_voice_stats(&mut html, data)?;
+
+    if !data.word_trends.spikes.is_empty() {
+        writeln!(html, "<section><h2>Words that spiked</h2>")?;
+        for spike in &data.word_trends.spikes {
+            writeln!(
+                html,
+                "<p><strong>{}</strong> {} times in {}, usually {:.1} a month</p>",
+                escape_html(&spike.word),
+                spike.count,
+                spike.month.format("%B %Y"),
+                spike.baseline
+            )?;
+        }
+        writeln!(html, "</section>")?;
+    }
+
+    if data.years.len() > 1 {
+        writeln!(
+            html,
+            "<section><h2>Year by year</h2><table><tr><th>Year</th><th>Messages</th><th>Active days</th><th>Top channel</th><th>Top DM</th><th>New guilds</th></tr>"
+        )?;
+        for summary in &data.years {
+            let top_channel = summary.top_channels.first().map_or("", |c| c.name.as_str());
+            let top_dm = summary.top_dms.first().map_or("", |dm| {
+                dm.user_data
+                    .as_ref()
+                    .map_or(dm.dm_user_id.as_str(), |u| u.username.as_str())
+            });
+            writeln!(
+                html,
+                "<tr><td>{}</td><td>{}</td><td>{}</td><td>{}</td><td>{}</td><td>{}</td></tr>",
+                summary.year,
+                summary.message_count,
+                summary.active_days,
+                escape_html(top_channel),
+                escape_html(top_dm),
+                summary.new_guilds.len()
+            )?;
+        }
+        writeln!(html, "</table></section>")?;
+    }
+
+    if let Some(user) = data.user.as_ref().filter(|u| !u.payments.is_empty()) {
+        writeln!(html, "<section><h2>Payments</h2>")?;
+        let mut totals: Vec<_> = data.payments.total.iter().collect();
+        totals.sort_by(|a, b| a.0.cmp(b.0));
+        for (currency, total) in totals {
+            writeln!(
+                html,
+                "<p><strong>{:.2} {}</strong> spent</p>",
+                total,
+                escape_html(&currency.to_uppercase())
+            )?;
+        }
+        if let (Some(total), Some(currency)) = (data.total_spent, &data.spent_currency)
+            && data.payments.total.len() > 1
+        {
+            writeln!(
+                html,
+                "<p>About <strong>{:.2} {}</strong> in total</p>",
+                total,
+                escape_html(&currency.to_uppercase())
+            )?;
+        }
+        if data.payments.by_year.len() > 1 {
+            writeln!(html, "<table><tr><th>Year</th><th>Spent</th></tr>")?;
+            for (year, totals) in &data.payments.by_year {
+                let mut totals: Vec<_> = totals.iter().collect();
+                totals.sort_by(|a, b| a.0.cmp(b.0));
+                let amounts: Vec<String> = totals
+                    .iter()
+                    .map(|(currency, total)| format!("{:.2} {}", total, currency.to_uppercase()))
+                    .collect();
+                writeln!(
+                    html,
+                    "<tr><td>{}</td><td>{}</td></tr>",
+                    year,
+                    escape_html(&amounts.join(", "))
+                )?;
+            }
+            writeln!(html, "</table>")?;
+        }
+        for period in &data.payments.subscriptions {
+            writeln!(
+                html,
+                "<p>{} from {} to {} ({} payments)</p>",
+                escape_html(&period.description),
+                period.start.format("%Y-%m-%d"),
+                period.end.format("%Y-%m-%d"),
+                period.payment_count
+            )?;
+        }
+        writeln!(
+            html,
+            "<table><tr><th>Date</th><th>Description</th><th>Amount</th><th>Status</th></tr>"
+        )?;
+        let mut payments: Vec<_> = user.payments.iter().collect();
+        payments.sort_by_key(|p| p.created_at);
+        for payment in payments {
+            let status = if payment.status == PaymentStatus::Completed && payment.is_refunded() {
+                format!("refunded {:.2}", payment.amount_refunded as f64 / 100.0)
+            } else {
+                payment.status.to_string()
+            };
+            writeln!(
+                html,
+                "<tr><td>{}</td><td>{}</td><td>{} {:.2}</td><td>{}</td></tr>",
+                payment.created_at.format("%Y-%m-%d"),
+                escape_html(&payment.description),
+                escape_html(&payment.currency.to_uppercase()),
+                payment.amount as f64 / 100.0,
+                escape_html(&status)
+            )?;
+        }
+        writeln!(html, "</table></section>")?;
+    }
+
+    writeln!(
+        html,
+        "<footer>Generated by dpkv on {}</footer>",
+        chrono::Utc::now().format("%Y-%m-%d")
+    )?;
+    writeln!(html, "</main></body></html>")?;
+
+    Ok(html)
+}
+
+fn write_highlights(html: &mut String, data: &ExtractedData) -> Result<()> {
+    let activity = &data.activity;
+    let mut lines: Vec<String> = Vec::new();
+    if let Some(streak) = &activity.longest_streak {
+        lines.push(format!(
+            "Longest streak: <strong>{} days</strong> ({} to {})",
+            streak.days, streak.start, streak.end
+        ));
+    }
+    if let Some(gap) = &activity.longest_gap {
+        lines.push(format!(
+            "Longest break: <strong>{} days</strong> ({} to {})",
+            gap.days, gap.start, gap.end
+        ));
+    }
+    if let Some(day) = &activity.busiest_day {
+        lines.push(format!(
+            "Busiest day: <strong>{}</strong> with {} messages, {} of them in {}",
+            day.date,
+            day.message_count,
+            day.top_channel_message_count,
+            escape_html(&day.top_channel)
+        ));
+    }
+    for (label, message) in [
+        ("First message", &activity.first_message),
+        ("Latest message", &activity.last_message),
+    ] {
+        if let Some(message) = message {
+            lines.push(format!(
+                "{}: <strong>{}</strong> in {}",
+                label,
+                message.sent_at.format("%Y-%m-%d %H:%M"),
+                escape_html(&message.channel_name)
+            ));
+        }
+    }
+    if activity.session_count > 0 {
+        lines.push(format!(
+            "<strong>{}</strong> sessions, usually about {:.0} minutes long",
+            activity.session_count, activity.median_session_minutes
+        ));
+    }
+
+    if lines.is_empty() {
+        return Ok(());
+    }
+    writeln!(html, "<section><h2>Highlights</h2>")?;
+    for line in lines {
+        writeln!(html, "<p>{}</p>", line)?;
+    }
+    writeln!(html, "</section>")?;
+    Ok(())
+}
+
+fn write_content_stats(html: &mut String, data: &ExtractedData) -> Result<()> {
+    let content = &data.content;
+    if data.message_count == 0 {
+        return Ok(());
+    }
+    writeln!(html, "<section><h2>Message types</h2><div class=\"stats\">")?;
+    for (label, share) in [
+        ("Questions", &content.questions),
+        ("With links", &content.links),
+        ("With code", &content.code),
+        ("Bot commands", &content.commands),
+        ("To bots", &content.replies_to_bots),
+        ("Shouting", &content.shouting),
+    ] {
+        writeln!(
+            html,
+            "<div class=\"stat\"><strong>{:.1}%</strong>{} ({})</div>",
+            share.share * 100.0,
+            label,
+            share.message_count
+        )?;
+    }
+    writeln!(html, "</div>")?;
+    if !content.code_languages.is_empty() {
+        let languages: Vec<String> = content
+            .code_languages
+            .iter()
+            .map(|l| format!("{} ({})", l.language, l.block_count))
+            .collect();
+        writeln!(html, "<p>Code blocks: {}</p>", escape_html(&languages.join(", ")))?;
+    }
+    if !content.top_commands.is_empty() {
+        let commands: Vec<String> = content
+            .top_commands
+            .iter()
+            .map(|c| format!("{} ({})", c.command, c.count))
+            .collect();
+        writeln!(html, "<p>Commands: {}</p>", escape_html(&commands.join(", ")))?;
+    }
+    writeln!(html, "</section>")?;
+    Ok(())
+}
+
+fn write_length_stats(html: &mut String, data: &ExtractedData) -> Result<()> {
+    let lengths = &data.lengths;
+    if data.message_count == 0 {
+        return Ok(());
+    }
+    writeln!(html, "<section><h2>Message length</h2>")?;
+    writeln!(
+        html,
+        "<p>Messages are <strong>{:.0}</strong> characters long on average, {:.0} at the median and {:.0} or less 95% of the time</p>",
+        lengths.average, lengths.median, lengths.p95
+    )?;
+    let max = lengths
+        .histogram
+        .iter()
+        .map(|b| b.message_count)
+        .max()
+        .unwrap_or(0)
+        .max(1);
+    for bucket in &lengths.histogram {
+        let label = match bucket.max {
+            Some(max) if max == bucket.min => max.to_string(),
+            Some(max) => format!("{}-{}", bucket.min, max),
+            None => format!("{}+", bucket.min),
+        };
+        writeln!(
+            html,
+            "<div class=\"bar-row\"><span>{}</span><div class=\"bar\" style=\"width:{:.1}%\"></div><span class=\"count\">{}</span></div>",
+            label,
+            bucket.message_count as f64 * 100.0 / max as f64,
+            bucket.message_count
+        )?;
+    }
+    let channels: Vec<_> = lengths.channels.iter().chain(&lengths.dms).collect();
+    if !channels.is_empty() {
+        writeln!(
+            html,
+            "<table><tr><th>Channel</th><th>Messages</th><th>Average</th><th>Median</th></tr>"
+        )?;
+        for channel in channels {
+            writeln!(
+                html,
+                "<tr><td>{}</td><td>{}</td><td>{:.0}</td><td>{:.0}</td></tr>",
+                escape_html(&channel.name),
+                channel.message_count,
+                channel.average,
+                channel.median
+            )?;
+        }
+        writeln!(html, "</table>")?;
+    }
+    writeln!(html, "</section>")?;
+    Ok(())
+}
+
+fn write_sentiment(html: &mut String, data: &ExtractedData) -> Result<()> {
+    let sentiment = &data.sentiment;
+    if sentiment.scored_message_count == 0 {
+        return Ok(());
+    }
+    writeln!(html, "<section><h2>Mood</h2>")?;
+    writeln!(
+        html,
+        "<p>Average sentiment <strong>{:+.2}</strong> over {} messages, from -1 (negative) to 1 (positive)</p>",
+        sentiment.average, sentiment.scored_message_count
+    )?;
+    for (label, days) in [
+        ("Most positive day", &sentiment.most_positive_days),
+        ("Most negative day", &sentiment.most_negative_days),
+    ] {
+        if let Some(day) = days.first() {
+            writeln!(
+                html,
+                "<p>{}: <strong>{}</strong> ({:+.2} over {} messages)</p>",
+                label, day.date, day.average, day.scored_message_count
+            )?;
+        }
+    }
+    let channels: Vec<_> = sentiment.channels.iter().chain(&sentiment.dms).collect();
+    if !channels.is_empty() {
+        writeln!(html, "<table><tr><th>Channel</th><th>Messages</th><th>Sentiment</th></tr>")?;
+        for channel in channels {
+            writeln!(
+                html,
+                "<tr><td>{}</td><td>{}</td><td>{:+.2}</td></tr>",
+                escape_html(&channel.name),
+                channel.scored_message_count,
+                channel.average
+            )?;
+        }
+        writeln!(html, "</table>")?;
+    }
+    writeln!(html, "</section>")?;
+    Ok(())
+}
+
+fn write_voice_stats(html: &mut String, data: &ExtractedData) -> Result<()> {
+    let voice = &data.voice;
+    if voice.session_count == 0 {
+        return Ok(());
+    }
+    writeln!(html, "<section><h2>Voice</h2>")?;
+    writeln!(
+        html,
+        "<p><strong>{}</strong> in voice over {} sessions</p>",
+        format_minutes(voice.total_minutes),
+        voice.session_count
+    )?;
+    if let Some(session) = &voice.longest_session {
+        let place = match (&session.channel_name, &session.guild_name) {
+            (Some(channel), Some(guild)) => format!(" in {} ({})", channel, guild),
+            (Some(name), None) | (None, Some(name)) => format!(" in {}", name),
+            (None, None) => String::new(),
+        };
+        writeln!(
+            html,
+            "<p>Longest session: <strong>{}</strong>{} on {}</p>",
+            format_minutes(session.minutes),
+            escape_html(&place),
+            session.start.format("%B %-d, %Y")
+        )?;
+    }
+
+    writeln!(html, "<div class=\"hours\">")?;
+    let max_hour = voice
+        .hours_minutes
+        .iter()
+        .copied()
+        .fold(0.0, f64::max)
+        .max(1.0);
+    for (hour, minutes) in voice.hours_minutes.iter().enumerate() {
+        writeln!(
+            html,
+            "<div title=\"{:.0} minutes\"><span class=\"bar\" style=\"height:{:.1}%\"></span>{}</div>",
+            minutes,
+            minutes * 100.0 / max_hour,
+            hour
+        )?;
+    }
+    writeln!(html, "</div>")?;
+
+    let places: Vec<_> = voice
+        .guilds
+        .iter()
+        .map(|t| ("Server", t))
+        .chain(voice.channels.iter().map(|t| ("Channel", t)))
+        .collect();
+    if !places.is_empty() {
+        writeln!(html, "<table><tr><th></th><th>Name</th><th>Sessions</th><th>Time</th></tr>")?;
+        for (kind, time) in places {
+            writeln!(
+                html,
+                "<tr><td>{}</td><td>{}</td><td>{}</td><td>{}</td></tr>",
+                kind,
+                escape_html(time.name.as_deref().unwrap_or(&time.id)),
+                time.session_count,
+                format_minutes(time.minutes)
+            )?;
+        }
+        writeln!(html, "</table>")?;
+    }
+    writeln!(html, "</section>")?;
+    Ok(())
+}
+
+fn format_minutes(minutes: f64) -> String {
+    let minutes = minutes.round() as u64;
+    if minutes < 60 {
+        format!("{} min", minutes)
+    } else {
+        format!("{} h {} min", minutes / 60, minutes % 60)
+    }
+}
+
+fn write_bar_chart(html: &mut String, title: &str, rows: &[(String, usize)]) -> Result<()> {
+    if rows.is_empty() {
+        return Ok(());
+    }
+    let max = rows.iter().map(|(_, count)| *count).max().unwrap_or(0).max(1);
+    writeln!(html, "<section><h2>{}</h2>", escape_html(title))?;
+    for (label, count) in rows {
+        writeln!(
+            html,
+            "<div class=\"bar-row\"><span title=\"{0}\">{0}</span><div class=\"bar\" style=\"width:{1:.1}%\"></div><span class=\"count\">{2}</span></div>",
+            escape_html(label),
+            *count as f64 * 100.0 / max as f64,
+            count
+        )?;
+    }
+    writeln!(html, "</section>")?;
+    Ok(())
+}
+
+fn avatar_src(data: &ExtractedData) -> Option<String> {
+    let user = data.user.as_ref()?;
+    Some(
+        user.avatar_data_uri()
+            .unwrap_or_else(|| user.default_avatar_data_uri()),
+    )
+}
+
+fn escape_html(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&#39;"),
+            _ => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::fixtures::{compute_stats, data, guild_channel, user};
+
+    #[test]
+    fn escapes_markup() {
+        assert_eq!(
+            escape_html(r#"<b>"Tom" & 'Jerry'</b>"#),
+            "&lt;b&gt;&quot;Tom&quot; &amp; &#39;Jerry&#39;&lt;/b&gt;"
+        );
+    }
+
+    #[test]
+    fn renders_a_self_contained_report() {
+        let mut data = data(vec![guild_channel(
+            "1",
+            "<script>alert(1)</script>",
+            &[("2023-01-01T10:00:00Z", "hello there")],
+        )]);
+        data.user = Some(user(serde_json::json!({"username": "<tester>"})));
+        compute_stats(&mut data);
+
+        let html = render_report(&data).unwrap();
+        assert!(html.starts_with("<!DOCTYPE html>"));
+        assert!(html.contains("<h1>&lt;tester&gt;</h1>"));
+        assert!(!html.contains("<script>alert(1)</script>"));
+        assert!(html.contains("src=\"data:image/"));
+        assert!(!html.contains("https://"));
+    }
+}
@@ -0,0 +1,7 @@
+mod csv;
+mod html;
+mod sqlite;
+
+pub use csv::export_csv;
+pub use html::export_html;
+pub use sqlite::{export_merged_sqlite_events, export_sqlite, export_sqlite_events};
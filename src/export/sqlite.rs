@@ -0,0 +1,409 @@
+use anyhow::Result;
+use rusqlite::{Connection, params};
+use std::collections::HashSet;
+use std::hash::{DefaultHasher, Hash, Hasher};
+use std::path::Path;
+
+use crate::Parser;
+use crate::models::{AnalyticsEvent, ExtractedData};
+use crate::snowflake;
+
+// Comments are kept in the DDL so they show up in `.schema` for anyone exploring the file.
+const SCHEMA: &str = "
+-- The account that requested the package.
+CREATE TABLE user (
+    id TEXT PRIMARY KEY,                  -- Discord user snowflake
+    username TEXT NOT NULL,
+    discriminator INTEGER NOT NULL,       -- 0 for accounts on the new username system
+    default_avatar_index INTEGER NOT NULL, -- which of Discord's six default avatars applies
+    created_at TEXT                       -- decoded from the id
+);
+
+-- Accounts on other services linked to the profile.
+CREATE TABLE connections (
+    service TEXT NOT NULL,                -- github, steam, spotify, ...
+    id TEXT NOT NULL,                     -- account id on that service
+    name TEXT NOT NULL,
+    verified INTEGER NOT NULL,
+    revoked INTEGER NOT NULL
+);
+
+-- Friends, blocked users and pending friend requests.
+CREATE TABLE relationships (
+    user_id TEXT NOT NULL,
+    username TEXT,
+    kind INTEGER NOT NULL,                -- 1 = friend, 2 = blocked, 3 = incoming, 4 = outgoing request
+    nickname TEXT,
+    note TEXT                             -- private note about the user, if any
+);
+
+-- Guilds listed in Servers/index.json.
+CREATE TABLE guilds (
+    id TEXT PRIMARY KEY,                  -- guild snowflake
+    name TEXT NOT NULL,
+    created_at TEXT                       -- decoded from the id
+);
+
+-- Every channel found under Messages/, including DMs.
+CREATE TABLE channels (
+    id TEXT PRIMARY KEY,                  -- channel snowflake
+    name TEXT NOT NULL,                   -- display name from Messages/index.json
+    type TEXT NOT NULL,                   -- dm, group_dm, guild_text, voice_text, thread, forum_post, other
+    guild_id TEXT,                        -- NULL for DMs, group DMs and channels of left guilds
+    guild_name TEXT,
+    recipients TEXT,                      -- JSON array of user ids, NULL for guild channels
+    created_at TEXT,                      -- decoded from the id, when a DM was opened
+    message_count INTEGER NOT NULL        -- rows in messages, attachment-only ones included
+);
+
+-- Messages sent by the account. Messages with only attachments have empty contents.
+CREATE TABLE messages (
+    id INTEGER PRIMARY KEY,               -- message snowflake
+    channel_id TEXT NOT NULL REFERENCES channels(id),
+    timestamp TEXT NOT NULL,              -- as written in the package, usually RFC 3339
+    contents TEXT NOT NULL,
+    length INTEGER NOT NULL               -- length of contents in characters
+);
+CREATE INDEX messages_channel ON messages(channel_id);
+CREATE INDEX messages_timestamp ON messages(timestamp);
+
+-- Attachment URLs, one row per file attached to a message.
+CREATE TABLE attachments (
+    message_id INTEGER NOT NULL REFERENCES messages(id),
+    channel_id TEXT NOT NULL REFERENCES channels(id),
+    url TEXT NOT NULL
+);
+
+-- Payments from Account/user.json.
+CREATE TABLE payments (
+    id TEXT,                              -- payment snowflake, may be empty in old packages
+    created_at TEXT NOT NULL,
+    currency TEXT NOT NULL,               -- lowercase ISO 4217 code
+    amount INTEGER NOT NULL,              -- in minor units (cents)
+    amount_refunded INTEGER NOT NULL,     -- in minor units, part of a completed payment paid back
+    status TEXT NOT NULL,                 -- completed, failed, refunded, reversed, pending, canceled
+    sku_id TEXT,
+    description TEXT NOT NULL
+);
+
+-- Events from activity/analytics. Only the common columns are kept.
+CREATE TABLE analytics_events (
+    event_type TEXT NOT NULL,
+    timestamp TEXT NOT NULL,
+    channel_id TEXT,
+    guild_id TEXT
+);
+CREATE INDEX analytics_events_type ON analytics_events(event_type);
+";
+
+/// Writes the full dataset to a new SQLite database at `output`, replacing any existing file.
+pub fn export_sqlite(data: &ExtractedData, output: &Path) -> Result<()> {
+    if output.exists() {
+        std::fs::remove_file(output)?;
+    }
+
+    let mut conn = Connection::open(output)?;
+    conn.execute_batch(SCHEMA)?;
+    let tx = conn.transaction()?;
+
+    if let Some(user) = &data.user {
+        tx.execute(
+            "INSERT INTO user (id, username, discriminator, default_avatar_index, created_at)
+             VALUES (?1, ?2, ?3, ?4, ?5)",
+            params![
+                user.id,
+                user.username,
+                user.discriminator,
+                user.default_avatar_index as i64,
+                user.created_at().map(|dt| dt.to_rfc3339()),
+            ],
+        )?;
+
+        let mut stmt = tx.prepare(
+            "INSERT INTO connections (service, id, name, verified, revoked) VALUES (?1, ?2, ?3, ?4, ?5)",
+        )?;
+        for connection in &user.connections {
+            stmt.execute(params![
+                connection.service,
+                connection.id,
+                connection.name,
+                connection.verified,
+                connection.revoked,
+            ])?;
+        }
+
+        let mut stmt = tx.prepare(
+            "INSERT INTO relationships (user_id, username, kind, nickname, note)
+             VALUES (?1, ?2, ?3, ?4, ?5)",
+        )?;
+        for relationship in &user.relationships {
+            stmt.execute(params![
+                relationship.id,
+                relationship.user.as_ref().map(|u| &u.username),
+                i32::from(relationship.kind),
+                relationship.nickname,
+                user.notes.get(&relationship.id),
+            ])?;
+        }
+
+        let mut stmt = tx.prepare(
+            "INSERT INTO payments
+                 (id, created_at, currency, amount, amount_refunded, status, sku_id, description)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)",
+        )?;
+        for payment in &user.payments {
+            stmt.execute(params![
+                payment.id,
+                payment.created_at.to_rfc3339(),
+                payment.currency,
+                payment.amount,
+                payment.amount_refunded,
+                payment.status.to_string(),
+                payment.sku_id,
+                payment.description,
+            ])?;
+        }
+    }
+
+    {
+        let mut stmt = tx.prepare("INSERT INTO guilds (id, name, created_at) VALUES (?1, ?2, ?3)")?;
+        for (id, name) in &data.guilds {
+            let created_at = snowflake::created_at(id).map(|dt| dt.to_rfc3339());
+            stmt.execute(params![id, name, created_at])?;
+        }
+    }
+
+    {
+        let mut channel_stmt = tx.prepare(
+            "INSERT OR REPLACE INTO channels
+                 (id, name, type, guild_id, guild_name, recipients, created_at, message_count)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)",
+        )?;
+        let mut message_stmt = tx.prepare(
+            "INSERT OR REPLACE INTO messages (id, channel_id, timestamp, contents, length)
+             VALUES (?1, ?2, ?3, ?4, ?5)",
+        )?;
+        let mut attachment_stmt = tx.prepare(
+            "INSERT INTO attachments (message_id, channel_id, url) VALUES (?1, ?2, ?3)",
+        )?;
+
+        for parsed in &data.channels {
+            let channel = &parsed.channel;
+            let guild = channel.guild.as_ref();
+            let recipients = match &channel.recipients {
+                Some(r) => Some(serde_json::to_string(r)?),
+                None => None,
+            };
+            channel_stmt.execute(params![
+                channel.id,
+                parsed.name,
+                channel.kind().name(),
+                guild.map(|g| g.id.as_str()).filter(|id| !id.is_empty()),
+                guild.map(|g| g.name.as_str()),
+                recipients,
+                channel.created_at().map(|dt| dt.to_rfc3339()),
+                (parsed.messages.len() + parsed.attachment_only.len()) as i64,
+            ])?;
+
+            for message in parsed.messages.iter().chain(&parsed.attachment_only) {
+                message_stmt.execute(params![
+                    message.id as i64,
+                    channel.id,
+                    message.timestamp,
+                    message.contents,
+                    message.length as i64,
+                ])?;
+                for url in &message.attachments {
+                    attachment_stmt.execute(params![message.id as i64, channel.id, url])?;
+                }
+            }
+        }
+    }
+
+    tx.commit()?;
+    eprintln!("[debug] Exported SQLite database to {}", output.display());
+    Ok(())
+}
+
+/// Fills the `analytics_events` table of a database written by [`export_sqlite`], streaming
+/// the events from the package at `package` so they never sit in memory. `map` runs on each
+/// event first, e.g. [`crate::redact::event_redactor`].
+pub fn export_sqlite_events<F>(package: &Path, output: &Path, map: F) -> Result<usize>
+where
+    F: FnMut(&mut AnalyticsEvent),
+{
+    export_merged_sqlite_events(&[package], output, map)
+}
+
+/// Like [`export_sqlite_events`] for the packages of a merge. Events an earlier package
+/// already had, with the same type, timestamp and channel, are skipped, as in
+/// [`Parser::merge_packages`].
+pub fn export_merged_sqlite_events<P, F>(packages: &[P], output: &Path, mut map: F) -> Result<usize>
+where
+    P: AsRef<Path>,
+    F: FnMut(&mut AnalyticsEvent),
+{
+    let mut conn = Connection::open(output)?;
+    let tx = conn.transaction()?;
+    let mut count = 0;
+    // Only a hash per event is kept, and only when there is something to deduplicate.
+    let mut seen: Option<HashSet<u64>> = (packages.len() > 1).then(HashSet::new);
+    {
+        let mut stmt = tx.prepare(
+            "INSERT INTO analytics_events (event_type, timestamp, channel_id, guild_id)
+             VALUES (?1, ?2, ?3, ?4)",
+        )?;
+        for package in packages {
+            Parser::read_analytics_events(package.as_ref(), |mut event| {
+                if let Some(seen) = seen.as_mut() {
+                    let mut hasher = DefaultHasher::new();
+                    (&event.event_type, &event.timestamp, &event.channel_id).hash(&mut hasher);
+                    if !seen.insert(hasher.finish()) {
+                        return Ok(());
+                    }
+                }
+                map(&mut event);
+                stmt.execute(params![
+                    event.event_type,
+                    event.timestamp,
+                    event.channel_id,
+                    event.guild_id,
+                ])?;
+                count += 1;
+                Ok(())
+            })?;
+        }
+    }
+    tx.commit()?;
+    eprintln!("[debug] Exported {} analytics events to {}", count, output.display());
+    Ok(count)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::fixtures::{USER_ID, package, temp_path};
+
+    #[test]
+    fn streams_every_event_from_the_package() {
+        let events = [
+            r#"{"event_type": "app_opened", "timestamp": "2023-01-01T09:00:00Z"}"#,
+            r#"{"event_type": "join_call", "timestamp": "2023-01-01T10:00:00Z", "channel_id": "5"}"#,
+            r#"{"event_type": "leave_call", "timestamp": "2023-01-01T10:30:00Z", "channel_id": "5"}"#,
+        ];
+        let archive = package(&[(
+            "activity/analytics/events-2023-00000-of-00001.json",
+            events.join("\n"),
+        )]);
+        let zip = temp_path("events.zip");
+        let db = temp_path("events.sqlite");
+        std::fs::write(&zip, archive.into_inner().into_inner()).unwrap();
+        let _ = std::fs::remove_file(&db);
+
+        export_sqlite(&ExtractedData::default(), &db).unwrap();
+        let count = export_sqlite_events(&zip, &db, |event| {
+            event.channel_id = event.channel_id.as_ref().map(|id| format!("x{}", id));
+        })
+        .unwrap();
+        assert_eq!(count, 3);
+
+        let conn = Connection::open(&db).unwrap();
+        let rows: Vec<(String, Option<String>)> = conn
+            .prepare("SELECT event_type, channel_id FROM analytics_events ORDER BY rowid")
+            .unwrap()
+            .query_map([], |row| Ok((row.get(0)?, row.get(1)?)))
+            .unwrap()
+            .collect::<rusqlite::Result<_>>()
+            .unwrap();
+        assert_eq!(rows[0], ("app_opened".to_string(), None));
+        assert_eq!(rows[2], ("leave_call".to_string(), Some("x5".to_string())));
+
+        drop(conn);
+        std::fs::remove_file(&zip).unwrap();
+        std::fs::remove_file(&db).unwrap();
+    }
+
+    #[test]
+    fn empty_package_exports_empty_tables() {
+        let zip = temp_path("empty.zip");
+        let db = temp_path("empty.sqlite");
+        std::fs::write(&zip, package(&[]).into_inner().into_inner()).unwrap();
+        let _ = std::fs::remove_file(&db);
+
+        export_sqlite(&ExtractedData::default(), &db).unwrap();
+        assert_eq!(export_sqlite_events(&zip, &db, |_| {}).unwrap(), 0);
+        let conn = Connection::open(&db).unwrap();
+        let tables = ["user", "channels", "messages", "attachments", "analytics_events"];
+        for table in tables {
+            let sql = format!("SELECT COUNT(*) FROM {}", table);
+            let rows: i64 = conn.query_row(&sql, [], |row| row.get(0)).unwrap();
+            assert_eq!(rows, 0, "{}", table);
+        }
+
+        drop(conn);
+        std::fs::remove_file(&zip).unwrap();
+        std::fs::remove_file(&db).unwrap();
+    }
+
+    #[test]
+    fn merged_events_skip_duplicates() {
+        let shared = r#"{"event_type": "app_opened", "timestamp": "2023-01-01T09:00:00Z"}"#;
+        let later = r#"{"event_type": "app_opened", "timestamp": "2023-01-02T09:00:00Z"}"#;
+        let paths = [temp_path("merge-old.zip"), temp_path("merge-new.zip")];
+        for (path, events) in paths.iter().zip([vec![shared], vec![shared, later]]) {
+            let archive = package(&[(
+                "activity/analytics/events-2023-00000-of-00001.json",
+                events.join("\n"),
+            )]);
+            std::fs::write(path, archive.into_inner().into_inner()).unwrap();
+        }
+        let db = temp_path("merge-events.sqlite");
+        let _ = std::fs::remove_file(&db);
+
+        export_sqlite(&ExtractedData::default(), &db).unwrap();
+        assert_eq!(export_merged_sqlite_events(&paths, &db, |_| {}).unwrap(), 2);
+
+        for path in paths.iter().chain([&db]) {
+            std::fs::remove_file(path).unwrap();
+        }
+    }
+
+    #[test]
+    fn keeps_attachment_only_messages() {
+        let messages = r#"[
+            {"ID": 1001, "Timestamp": "2023-01-01T10:00:00+00:00", "Contents": "look at this",
+             "Attachments": ""},
+            {"ID": 1002, "Timestamp": "2023-01-01T10:01:00+00:00", "Contents": "",
+             "Attachments": "https://cdn.discordapp.com/attachments/1/2/cat.png"},
+            {"ID": 1003, "Timestamp": "2023-01-01T10:02:00+00:00", "Contents": "",
+             "Attachments": ""}
+        ]"#;
+        let archive = package(&[
+            (
+                "Account/user.json",
+                format!(r#"{{"id": "{}", "username": "tester", "discriminator": 0}}"#, USER_ID),
+            ),
+            (
+                "Messages/c800000000000000001/channel.json",
+                r#"{"id": "800000000000000001", "type": 0, "name": "general"}"#.to_string(),
+            ),
+            ("Messages/c800000000000000001/messages.json", messages.to_string()),
+            ("Messages/index.json", r#"{"800000000000000001": "general"}"#.to_string()),
+        ]);
+        let data = Parser::new().parse_archive(archive, |_| {}).unwrap();
+        // Only the text message counts towards the statistics.
+        assert_eq!(data.message_count, 1);
+
+        let db = temp_path("attachments.sqlite");
+        export_sqlite(&data, &db).unwrap();
+        let conn = Connection::open(&db).unwrap();
+        let query = |sql: &str| -> i64 { conn.query_row(sql, [], |row| row.get(0)).unwrap() };
+        assert_eq!(query("SELECT COUNT(*) FROM messages"), 2);
+        assert_eq!(query("SELECT message_count FROM channels"), 2);
+        assert_eq!(query("SELECT message_id FROM attachments"), 1002);
+        assert_eq!(query("SELECT length FROM messages WHERE id = 1002"), 0);
+
+        drop(conn);
+        std::fs::remove_file(&db).unwrap();
+    }
+}
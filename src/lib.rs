@@ -0,0 +1,28 @@
+//! Parser and exporters for Discord data packages.
+//!
+//! ```no_run
+//! use dpkv::Parser;
+//!
+//! # fn main() -> anyhow::Result<()> {
+//! let data = Parser::new().parse_file("package.zip")?;
+//! println!("{} messages in {} channels", data.message_count, data.channel_count);
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! The warp server lives behind the `server` feature; with default features off the crate
+//! builds without Node or any GUI libraries.
+
+pub mod cache;
+pub mod currency;
+pub mod export;
+pub mod models;
+pub mod parser;
+pub mod redact;
+pub mod sentiment;
+pub mod snowflake;
+#[cfg(feature = "server")]
+pub mod server;
+
+pub use models::ExtractedData;
+pub use parser::{Parser, ParserOptions};
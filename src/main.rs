@@ -1,24 +1,253 @@
-use warp::Filter;
+use clap::Parser as _;
+use std::fs::File;
+use std::io::BufReader;
+use std::path::Path;
+#[cfg(feature = "server")]
+use std::path::PathBuf;
 
-mod models;
-mod parser;
+mod cli;
+
+use cli::{Cli, Command, ExportFormat};
+use dpkv::cache::PackageCache;
+use dpkv::currency::{self, ExchangeRates};
+use dpkv::redact::{self, RedactionOptions};
+use dpkv::sentiment::{self, Lexicon};
+use dpkv::{ExtractedData, Parser, export};
+#[cfg(feature = "server")]
+use dpkv::server::ServerOptions;
+use zip::ZipArchive;
 
 #[tokio::main]
 async fn main() -> Result<(), Box<dyn std::error::Error>> {
-    let api = warp::path("api")
-        .and(warp::path("data"))
-        .and(warp::get())
-        .map(move || {
-            warp::reply::json(&serde_json::json!({
-                "message": "This is where the extracted data would be returned."
-            }))
-        });
+    let cli = Cli::parse();
+    let options = LoadOptions::from_cli(&cli)?;
+    let use_cache = !cli.no_cache;
+
+    match cli.command {
+        Some(Command::Export {
+            package,
+            format,
+            output,
+        }) => {
+            let data = load_package(open_cache(use_cache).as_mut(), &package, &options)?;
+            export(&data, &[&package], format, &output, &options)?;
+            println!("Exported {} to {}", package.display(), output.display());
+        }
+        Some(Command::Report { package, output }) => {
+            let data = load_package(open_cache(use_cache).as_mut(), &package, &options)?;
+            export::export_html(&data, &output)?;
+            println!("Wrote report for {} to {}", package.display(), output.display());
+        }
+        Some(Command::Diff { older, newer, json }) => {
+            let mut cache = open_cache(use_cache);
+            let old = load_package(cache.as_mut(), &older, &options)?;
+            let new = load_package(cache.as_mut(), &newer, &options)?;
+            let diff = Parser::diff_extracted(&old, &new);
+            if json {
+                println!("{}", serde_json::to_string_pretty(&diff)?);
+            } else {
+                print!("{}", diff);
+            }
+        }
+        Some(Command::Merge {
+            packages,
+            json,
+            output,
+            format,
+        }) => {
+            let archives = packages
+                .iter()
+                .map(|path| {
+                    let archive = ZipArchive::new(BufReader::new(File::open(path)?))?;
+                    Ok((path.display().to_string(), archive))
+                })
+                .collect::<anyhow::Result<Vec<_>>>()?;
+            let (data, report) = Parser::new()
+                .merge_packages(archives, |message| eprintln!("{}", message))?;
+            let data = post_process(data, &options);
+            let report = match &options.redaction {
+                Some(redaction) => redact::redact_merge_report(&report, redaction),
+                None => report,
+            };
+            if json {
+                println!("{}", serde_json::to_string_pretty(&report)?);
+            } else {
+                print!("{}", report);
+                println!(
+                    "Result: {} messages in {} channels",
+                    data.message_count,
+                    data.channels.len()
+                );
+            }
+            if let Some(output) = output {
+                export(&data, &packages, format, &output, &options)?;
+                eprintln!("Exported the merged packages to {}", output.display());
+            }
+        }
+        Some(Command::Wrapped { package, year }) => {
+            let data = load_package(open_cache(use_cache).as_mut(), &package, &options)?;
+            let summary = match year {
+                Some(year) => data.years.iter().find(|y| y.year == year),
+                None => data.years.last(),
+            };
+            match summary {
+                Some(summary) => print!("{}", summary),
+                None if year.is_some() => {
+                    return Err(format!("No messages in {}", year.unwrap_or_default()).into());
+                }
+                None => return Err("No messages in the package".into()),
+            }
+        }
+        #[cfg(feature = "server")]
+        Some(Command::Serve {
+            package,
+            cdn_avatars,
+        }) => serve(package, cdn_avatars, use_cache, options).await?,
+        #[cfg(feature = "server")]
+        None => serve(cli.package, cli.cdn_avatars, use_cache, options).await?,
+        #[cfg(not(feature = "server"))]
+        None => {
+            let _ = (cli.package, cli.cdn_avatars);
+            return Err("dpkv was built without the server feature, use a subcommand".into());
+        }
+    }
+
+    Ok(())
+}
+
+/// What to do with a package after it is parsed or loaded from the cache.
+struct LoadOptions {
+    redaction: Option<RedactionOptions>,
+    rates: Option<ExchangeRates>,
+    currency: Option<String>,
+    lexicon: Option<Lexicon>,
+}
+
+impl LoadOptions {
+    fn from_cli(cli: &Cli) -> anyhow::Result<Self> {
+        let redaction = match &cli.redact_words {
+            Some(path) => Some(
+                RedactionOptions::default().blocklist(RedactionOptions::load_blocklist(path)?),
+            ),
+            None if cli.redact => Some(RedactionOptions::default()),
+            None => None,
+        };
+        // A stored salt keeps pseudonyms stable between runs without ever being written out.
+        let redaction = match redaction {
+            Some(redaction) => Some(redaction.salt(RedactionOptions::load_salt(
+                &RedactionOptions::default_salt_path(),
+            )?)),
+            None => None,
+        };
 
-    let static_files = warp::fs::dir("ui/dist")
-        .or(warp::path::end().and(warp::fs::file("ui/dist/index.html")));
+        let rates = match &cli.rates {
+            Some(path) => Some(ExchangeRates::load(path)?),
+            None if cli.currency.is_some() => Some(ExchangeRates::bundled().clone()),
+            None => None,
+        };
+        if let (Some(rates), Some(currency)) = (&rates, &cli.currency)
+            && !rates.supports(currency)
+        {
+            anyhow::bail!("No exchange rates for {}, add them with --rates", currency);
+        }
 
-    let routes = api.or(static_files);
+        let lexicon = match &cli.lexicon {
+            Some(path) => Some(Lexicon::load(path)?),
+            None => None,
+        };
+
+        Ok(Self {
+            redaction,
+            rates,
+            currency: cli.currency.clone(),
+            lexicon,
+        })
+    }
+}
+
+/// Opens the package cache, or returns `None` when it is disabled or can't be opened so commands
+/// still work without it.
+fn open_cache(enabled: bool) -> Option<PackageCache> {
+    if !enabled {
+        return None;
+    }
+    match PackageCache::open(&PackageCache::default_path()) {
+        Ok(cache) => Some(cache),
+        Err(e) => {
+            eprintln!("[debug] Cache unavailable, parsing without it: {}", e);
+            None
+        }
+    }
+}
+
+fn load_package(
+    cache: Option<&mut PackageCache>,
+    path: &Path,
+    options: &LoadOptions,
+) -> anyhow::Result<ExtractedData> {
+    let mut parser = Parser::new();
+    let data = match cache {
+        Some(cache) => cache.extract(&mut parser, path, |message| eprintln!("{}", message))?,
+        None => parser.parse_file(path)?,
+    };
+    Ok(post_process(data, options))
+}
+
+/// Applies the conversion, lexicon and redaction options to freshly loaded data.
+fn post_process(mut data: ExtractedData, options: &LoadOptions) -> ExtractedData {
+    if let Some(rates) = &options.rates {
+        currency::convert_spending(&mut data, rates, options.currency.as_deref());
+    }
+    if let Some(lexicon) = &options.lexicon {
+        sentiment::score_sentiment(&mut data, lexicon);
+    }
+    match &options.redaction {
+        Some(redaction) => redact::redact(&data, redaction),
+        None => data,
+    }
+}
+
+/// Exports `data`, loaded from `packages`. The SQLite export streams its analytics events from
+/// the packages themselves.
+fn export<P: AsRef<Path>>(
+    data: &ExtractedData,
+    packages: &[P],
+    format: ExportFormat,
+    output: &Path,
+    options: &LoadOptions,
+) -> anyhow::Result<()> {
+    match format {
+        ExportFormat::Sqlite => {
+            export::export_sqlite(data, output)?;
+            match &options.redaction {
+                Some(redaction) => export::export_merged_sqlite_events(
+                    packages,
+                    output,
+                    redact::event_redactor(redaction),
+                )?,
+                None => export::export_merged_sqlite_events(packages, output, |_| {})?,
+            };
+        }
+        ExportFormat::Csv => export::export_csv(data, output)?,
+    }
+    Ok(())
+}
 
-    warp::serve(routes).run(([127, 0, 0, 1], 3030)).await;
+#[cfg(feature = "server")]
+async fn serve(
+    package: Option<PathBuf>,
+    cdn_avatars: bool,
+    use_cache: bool,
+    options: LoadOptions,
+) -> anyhow::Result<()> {
+    let mut cache = open_cache(use_cache);
+    let data = match package {
+        Some(path) => Some(load_package(cache.as_mut(), &path, &options)?),
+        None => None,
+    };
+    let server_options = ServerOptions::default()
+        .cdn_avatars(cdn_avatars)
+        .redaction(options.redaction);
+    dpkv::server::run(cache, data, server_options).await;
     Ok(())
-}
\ No newline at end of file
+}
@@ -0,0 +1,192 @@
+use serde::{Deserialize, Deserializer, Serialize};
+
+/// An external account linked to the profile (GitHub, Steam, Spotify, ...).
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[non_exhaustive]
+pub struct Connection {
+    #[serde(rename = "type")]
+    pub service: String,
+    #[serde(default)]
+    pub id: String,
+    #[serde(default)]
+    pub name: String,
+    #[serde(default)]
+    pub verified: bool,
+    #[serde(default)]
+    pub revoked: bool,
+    /// 1 when the connection is shown on the profile.
+    #[serde(default)]
+    pub visibility: i32,
+}
+
+/// Another user the account is friends with, has blocked or has a pending request with.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[non_exhaustive]
+pub struct Relationship {
+    pub id: String,
+    #[serde(rename = "type")]
+    pub kind: RelationshipKind,
+    #[serde(default)]
+    pub nickname: Option<String>,
+    #[serde(default)]
+    pub user: Option<super::user::UserData>,
+}
+
+/// Discord's relationship type codes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(from = "i32", into = "i32")]
+#[non_exhaustive]
+pub enum RelationshipKind {
+    Friend,
+    Blocked,
+    IncomingRequest,
+    OutgoingRequest,
+    Implicit,
+    Unknown(i32),
+}
+
+impl From<i32> for RelationshipKind {
+    fn from(code: i32) -> Self {
+        match code {
+            1 => RelationshipKind::Friend,
+            2 => RelationshipKind::Blocked,
+            3 => RelationshipKind::IncomingRequest,
+            4 => RelationshipKind::OutgoingRequest,
+            5 => RelationshipKind::Implicit,
+            code => RelationshipKind::Unknown(code),
+        }
+    }
+}
+
+impl From<RelationshipKind> for i32 {
+    fn from(kind: RelationshipKind) -> Self {
+        match kind {
+            RelationshipKind::Friend => 1,
+            RelationshipKind::Blocked => 2,
+            RelationshipKind::IncomingRequest => 3,
+            RelationshipKind::OutgoingRequest => 4,
+            RelationshipKind::Implicit => 5,
+            RelationshipKind::Unknown(code) => code,
+        }
+    }
+}
+
+/// Notification settings for one guild. `guild_id` is missing for the DM settings.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[non_exhaustive]
+pub struct GuildSettings {
+    #[serde(default)]
+    pub guild_id: Option<String>,
+    #[serde(default)]
+    pub muted: bool,
+    /// 0 = all messages, 1 = only mentions, 2 = nothing, 3 = guild default.
+    #[serde(default)]
+    pub message_notifications: i32,
+    #[serde(default)]
+    pub suppress_everyone: bool,
+    #[serde(default)]
+    pub suppress_roles: bool,
+}
+
+/// Something the account owns: a Nitro subscription, a game, a boost, ...
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[non_exhaustive]
+pub struct Entitlement {
+    pub id: String,
+    pub sku_id: String,
+    #[serde(default)]
+    pub application_id: Option<String>,
+    #[serde(rename = "type", default)]
+    pub kind: i32,
+    #[serde(default)]
+    pub deleted: bool,
+    #[serde(default)]
+    pub consumed: bool,
+}
+
+/// A gift code bought by the account.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[non_exhaustive]
+pub struct GiftCode {
+    pub code: String,
+    #[serde(default)]
+    pub sku_id: Option<String>,
+    #[serde(default)]
+    pub uses: u32,
+    #[serde(default)]
+    pub max_uses: u32,
+    #[serde(default)]
+    pub expires_at: Option<String>,
+}
+
+/// For `user.json` sections that are `null` instead of empty in some packages.
+pub(crate) fn null_as_default<'de, D, T>(deserializer: D) -> Result<T, D::Error>
+where
+    D: Deserializer<'de>,
+    T: Default + Deserialize<'de>,
+{
+    Ok(Option::<T>::deserialize(deserializer)?.unwrap_or_default())
+}
+
+/// Discriminators are numbers in `user.json` but strings in relationships.
+pub(crate) fn lenient_discriminator<'de, D>(deserializer: D) -> Result<u16, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum Raw {
+        Number(u16),
+        Text(String),
+    }
+
+    match Option::<Raw>::deserialize(deserializer)? {
+        Some(Raw::Number(n)) => Ok(n),
+        Some(Raw::Text(s)) => s.parse().map_err(serde::de::Error::custom),
+        None => Ok(0),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::models::User;
+    use serde_json::json;
+
+    #[test]
+    fn reads_relationships_connections_and_null_sections() {
+        let user: User = serde_json::from_value(json!({
+            "id": "123456789012345678",
+            "username": "tester",
+            "discriminator": "0042",
+            "relationships": [
+                {"id": "1", "type": 1, "user": {"id": "1", "username": "a", "discriminator": "7",
+                    "avatar": null}},
+                {"id": "2", "type": 1},
+                {"id": "3", "type": 2},
+                {"id": "4", "type": 9},
+            ],
+            "connections": [
+                {"type": "steam", "id": "1"},
+                {"type": "github", "id": "2"},
+                {"type": "github", "id": "3"},
+                {"type": "twitch", "id": "4", "revoked": true},
+            ],
+            "guild_settings": null,
+            "notes": null,
+        }))
+        .unwrap();
+
+        assert_eq!(user.discriminator, 42);
+        assert_eq!(
+            user.relationships[0].user.as_ref().unwrap().discriminator,
+            7
+        );
+        assert_eq!((user.friend_count(), user.blocked_count()), (2, 1));
+        assert_eq!(
+            user.relationships[3].kind,
+            super::RelationshipKind::Unknown(9)
+        );
+        assert_eq!(user.linked_services(), ["github", "steam"]);
+        assert!(user.guild_settings.is_empty() && user.notes.is_empty());
+    }
+}
@@ -0,0 +1,74 @@
+use chrono::{DateTime, NaiveDate, Utc};
+use serde::{Deserialize, Serialize};
+
+/// Headline numbers about when the account was active.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[non_exhaustive]
+pub struct ActivityStats {
+    /// Longest run of consecutive days with at least one message.
+    pub longest_streak: Option<DayRange>,
+    /// Longest run of days without any message, between two active days.
+    pub longest_gap: Option<DayRange>,
+    pub busiest_day: Option<BusiestDay>,
+    pub first_message: Option<MessageHighlight>,
+    pub last_message: Option<MessageHighlight>,
+    /// Bursts of messages across all channels, split wherever the account was quiet for
+    /// longer than the session gap.
+    pub session_count: usize,
+    pub median_session_minutes: f64,
+    pub longest_session: Option<Session>,
+    /// Channels with the most sessions and how long a session in them usually lasts.
+    pub channel_sessions: Vec<ChannelSessions>,
+}
+
+/// An inclusive range of days.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[non_exhaustive]
+pub struct DayRange {
+    pub start: NaiveDate,
+    pub end: NaiveDate,
+    pub days: usize,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[non_exhaustive]
+pub struct BusiestDay {
+    pub date: NaiveDate,
+    pub message_count: usize,
+    pub top_channel_id: String,
+    pub top_channel: String,
+    pub top_channel_message_count: usize,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[non_exhaustive]
+pub struct MessageHighlight {
+    pub channel_id: String,
+    pub channel_name: String,
+    pub sent_at: DateTime<Utc>,
+    pub contents: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[non_exhaustive]
+pub struct Session {
+    pub start: DateTime<Utc>,
+    pub end: DateTime<Utc>,
+    pub message_count: usize,
+}
+
+impl Session {
+    pub fn minutes(&self) -> f64 {
+        (self.end - self.start).num_seconds() as f64 / 60.0
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[non_exhaustive]
+pub struct ChannelSessions {
+    pub channel_id: String,
+    pub name: String,
+    pub session_count: usize,
+    pub median_session_minutes: f64,
+    pub median_session_messages: f64,
+}
@@ -0,0 +1,13 @@
+use serde::{Deserialize, Serialize};
+
+/// A single tracked event from the analytics logs. Only the common fields are kept.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[non_exhaustive]
+pub struct AnalyticsEvent {
+    pub event_type: String,
+    pub timestamp: String,
+    #[serde(default)]
+    pub channel_id: Option<String>,
+    #[serde(default)]
+    pub guild_id: Option<String>,
+}
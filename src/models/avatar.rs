@@ -0,0 +1,126 @@
+use base64::Engine;
+use base64::engine::general_purpose::STANDARD;
+
+use super::user::User;
+use crate::snowflake::Snowflake;
+
+const DEFAULT_AVATARS: [&str; 6] = [
+    include_str!("../../assets/default-avatars/0.svg"),
+    include_str!("../../assets/default-avatars/1.svg"),
+    include_str!("../../assets/default-avatars/2.svg"),
+    include_str!("../../assets/default-avatars/3.svg"),
+    include_str!("../../assets/default-avatars/4.svg"),
+    include_str!("../../assets/default-avatars/5.svg"),
+];
+
+impl User {
+    /// MIME type of the packaged avatar, sniffed from its bytes with the file extension as fallback.
+    pub fn avatar_mime(&self) -> Option<&'static str> {
+        let bytes = self.avatar.as_deref()?;
+        Some(detect_image_mime(bytes, self.avatar_extension.as_deref()))
+    }
+
+    pub fn avatar_base64(&self) -> Option<String> {
+        self.avatar.as_ref().map(|bytes| STANDARD.encode(bytes))
+    }
+
+    /// The avatar as a `data:` URI, ready to drop into an `<img>` tag.
+    pub fn avatar_data_uri(&self) -> Option<String> {
+        Some(format!(
+            "data:{};base64,{}",
+            self.avatar_mime()?,
+            self.avatar_base64()?
+        ))
+    }
+
+    /// The bundled default avatar, as used when the account has no custom one.
+    pub fn default_avatar_svg(&self) -> &'static str {
+        default_avatar_svg(self.default_avatar_index)
+    }
+
+    pub fn default_avatar_data_uri(&self) -> String {
+        format!(
+            "data:image/svg+xml;base64,{}",
+            STANDARD.encode(self.default_avatar_svg())
+        )
+    }
+
+    /// Discord's CDN copy of the default avatar. Only for callers that opted into network access.
+    pub fn default_avatar_cdn_url(&self) -> String {
+        format!(
+            "https://cdn.discordapp.com/embed/avatars/{}.png",
+            self.default_avatar_index
+        )
+    }
+}
+
+/// Accounts on the new username system (discriminator 0) pick from six avatars based on
+/// their id, legacy accounts from the first five based on the discriminator.
+pub fn default_avatar_index(user_id: &str, discriminator: u16) -> usize {
+    if discriminator == 0 {
+        (Snowflake::parse(user_id).map_or(0, Snowflake::timestamp_ms) % 6) as usize
+    } else {
+        (discriminator % 5) as usize
+    }
+}
+
+pub fn default_avatar_svg(index: usize) -> &'static str {
+    DEFAULT_AVATARS[index % DEFAULT_AVATARS.len()]
+}
+
+pub fn detect_image_mime(bytes: &[u8], extension: Option<&str>) -> &'static str {
+    if bytes.starts_with(b"\x89PNG\r\n\x1a\n") {
+        return "image/png";
+    }
+    if bytes.starts_with(b"GIF87a") || bytes.starts_with(b"GIF89a") {
+        return "image/gif";
+    }
+    if bytes.starts_with(&[0xFF, 0xD8, 0xFF]) {
+        return "image/jpeg";
+    }
+    if bytes.len() >= 12 && &bytes[..4] == b"RIFF" && &bytes[8..12] == b"WEBP" {
+        return "image/webp";
+    }
+
+    match extension.map(|e| e.to_ascii_lowercase()).as_deref() {
+        Some("png") => "image/png",
+        Some("gif") => "image/gif",
+        Some("jpg") | Some("jpeg") => "image/jpeg",
+        Some("webp") => "image/webp",
+        _ => "application/octet-stream",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn picks_the_default_avatar() {
+        // New usernames use (id >> 22) % 6, legacy ones the discriminator % 5.
+        assert_eq!(default_avatar_index("175928847299117063", 0), 2);
+        assert_eq!(default_avatar_index("80351110224678912", 0), 5);
+        assert_eq!(default_avatar_index("80351110224678912", 1337), 2);
+        assert_eq!(default_avatar_index("not an id", 0), 0);
+        assert_eq!(default_avatar_svg(7), default_avatar_svg(1));
+    }
+
+    #[test]
+    fn detects_images_by_magic_bytes_first() {
+        assert_eq!(
+            detect_image_mime(b"\x89PNG\r\n\x1a\n....", Some("gif")),
+            "image/png"
+        );
+        assert_eq!(detect_image_mime(b"GIF89a", None), "image/gif");
+        assert_eq!(
+            detect_image_mime(&[0xFF, 0xD8, 0xFF, 0xE0], None),
+            "image/jpeg"
+        );
+        assert_eq!(
+            detect_image_mime(b"RIFF\0\0\0\0WEBPVP8 ", None),
+            "image/webp"
+        );
+        assert_eq!(detect_image_mime(b"????", Some("JPG")), "image/jpeg");
+        assert_eq!(detect_image_mime(b"????", None), "application/octet-stream");
+    }
+}
@@ -1,29 +1,227 @@
+use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 
+use crate::snowflake;
+
+/// A channel as described by `channel.json` in the package.
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[non_exhaustive]
 pub struct Channel {
     pub id: String,
+    /// Missing from the oldest packages, see [`Channel::kind`].
+    #[serde(rename = "type", default)]
+    pub channel_type: Option<ChannelType>,
     pub recipients: Option<Vec<String>>,
     pub guild: Option<Guild>,
 }
 
+impl Channel {
+    pub fn created_at(&self) -> Option<DateTime<Utc>> {
+        snowflake::created_at(&self.id)
+    }
+
+    /// The channel type, guessed from the guild and recipients when the package has none.
+    pub fn kind(&self) -> ChannelType {
+        if let Some(channel_type) = self.channel_type {
+            return channel_type;
+        }
+        match (&self.guild, self.recipients.as_ref().map(Vec::len)) {
+            (Some(_), _) => ChannelType::GuildText,
+            (None, Some(2)) => ChannelType::Dm,
+            (None, Some(n)) if n > 2 => ChannelType::GroupDm,
+            _ => ChannelType::Other,
+        }
+    }
+}
+
+/// The guild a channel belongs to.
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[non_exhaustive]
 pub struct Guild {
+    #[serde(default)]
+    pub id: String,
     pub name: String,
 }
 
-#[derive(Debug, Clone)]
+impl Guild {
+    pub fn created_at(&self) -> Option<DateTime<Utc>> {
+        snowflake::created_at(&self.id)
+    }
+}
+
+/// A channel together with every message the account sent in it.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[non_exhaustive]
+pub struct ParsedChannel {
+    pub channel: Channel,
+    pub name: String,
+    /// Messages with text. The statistics are built from these.
+    pub messages: Vec<super::message::ParsedMessage>,
+    /// Messages with attachments but no text, kept apart so they only reach the exports.
+    #[serde(default)]
+    pub attachment_only: Vec<super::message::ParsedMessage>,
+}
+
+/// A guild channel or thread ranked by message count.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[non_exhaustive]
 pub struct TopChannel {
     pub name: String,
     pub message_count: usize,
+    /// `None` for channels of guilds the account has left.
     pub guild_name: Option<String>,
+    pub channel_type: ChannelType,
+    pub created_at: Option<DateTime<Utc>>,
+    pub guild_created_at: Option<DateTime<Utc>>,
 }
 
-#[derive(Debug, Clone)]
+/// A direct message conversation ranked by message count.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[non_exhaustive]
 pub struct TopDM {
     pub id: String,
     pub dm_user_id: String,
     pub message_count: usize,
     pub user_data: Option<super::user::UserData>,
+    /// When the DM channel was opened.
+    pub started_at: Option<DateTime<Utc>>,
+}
+
+
+/// A group DM ranked by message count.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[non_exhaustive]
+pub struct TopGroupDM {
+    pub id: String,
+    pub name: String,
+    pub message_count: usize,
+    pub created_at: Option<DateTime<Utc>>,
+    /// Everyone in the group except the account itself. Users that aren't in the account's
+    /// relationships only have their id as username.
+    pub participants: Vec<super::user::UserData>,
+}
+
+/// What kind of conversation a channel is, from the `type` field of `channel.json`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize)]
+#[serde(rename_all = "snake_case")]
+#[non_exhaustive]
+pub enum ChannelType {
+    Dm,
+    GroupDm,
+    /// Text and announcement channels.
+    GuildText,
+    /// The text chat of a voice or stage channel.
+    VoiceText,
+    Thread,
+    /// A post in a forum or media channel.
+    ForumPost,
+    Other,
+}
+
+impl ChannelType {
+    pub fn from_code(code: i64) -> Self {
+        match code {
+            0 | 5 => ChannelType::GuildText,
+            1 => ChannelType::Dm,
+            2 | 13 => ChannelType::VoiceText,
+            3 => ChannelType::GroupDm,
+            10..=12 => ChannelType::Thread,
+            15 | 16 => ChannelType::ForumPost,
+            _ => ChannelType::Other,
+        }
+    }
+
+    /// Accepts Discord's names (`GUILD_TEXT`, `PUBLIC_THREAD`, ...) as well as our own.
+    pub fn from_name(name: &str) -> Self {
+        match name.to_ascii_uppercase().as_str() {
+            "DM" => ChannelType::Dm,
+            "GROUP_DM" => ChannelType::GroupDm,
+            "GUILD_TEXT" | "GUILD_ANNOUNCEMENT" | "GUILD_NEWS" => ChannelType::GuildText,
+            "GUILD_VOICE" | "GUILD_STAGE_VOICE" | "VOICE_TEXT" => ChannelType::VoiceText,
+            "PUBLIC_THREAD" | "PRIVATE_THREAD" | "ANNOUNCEMENT_THREAD" | "GUILD_NEWS_THREAD"
+            | "GUILD_PUBLIC_THREAD" | "GUILD_PRIVATE_THREAD" | "THREAD" => ChannelType::Thread,
+            "GUILD_FORUM" | "GUILD_MEDIA" | "FORUM_POST" => ChannelType::ForumPost,
+            _ => ChannelType::Other,
+        }
+    }
+
+    /// The name used in JSON and exports.
+    pub fn name(self) -> &'static str {
+        match self {
+            ChannelType::Dm => "dm",
+            ChannelType::GroupDm => "group_dm",
+            ChannelType::GuildText => "guild_text",
+            ChannelType::VoiceText => "voice_text",
+            ChannelType::Thread => "thread",
+            ChannelType::ForumPost => "forum_post",
+            ChannelType::Other => "other",
+        }
+    }
+
+    pub fn is_dm(self) -> bool {
+        matches!(self, ChannelType::Dm | ChannelType::GroupDm)
+    }
 }
 
+// Older packages store the numeric type, newer ones the name.
+impl<'de> Deserialize<'de> for ChannelType {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        #[derive(Deserialize)]
+        #[serde(untagged)]
+        enum Raw {
+            Code(i64),
+            Name(String),
+        }
+
+        Ok(match Raw::deserialize(deserializer)? {
+            Raw::Code(code) => ChannelType::from_code(code),
+            Raw::Name(name) => ChannelType::from_name(&name),
+        })
+    }
+}
+
+/// Channels and messages of one [`ChannelType`].
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[non_exhaustive]
+pub struct ChannelTypeStats {
+    pub channel_type: ChannelType,
+    pub channel_count: usize,
+    pub message_count: usize,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn channel(value: serde_json::Value) -> Channel {
+        serde_json::from_value(value).unwrap()
+    }
+
+    #[test]
+    fn reads_codes_and_names() {
+        let kind = |value| serde_json::from_value::<ChannelType>(value).unwrap();
+        assert_eq!(kind(json!(0)), ChannelType::GuildText);
+        assert_eq!(kind(json!(11)), ChannelType::Thread);
+        assert_eq!(kind(json!(15)), ChannelType::ForumPost);
+        assert_eq!(kind(json!("GUILD_STAGE_VOICE")), ChannelType::VoiceText);
+        assert_eq!(kind(json!("public_thread")), ChannelType::Thread);
+        assert_eq!(kind(json!("group_dm")), ChannelType::GroupDm);
+        assert_eq!(kind(json!(99)), ChannelType::Other);
+        assert_eq!(serde_json::to_value(ChannelType::ForumPost).unwrap(), json!("forum_post"));
+    }
+
+    #[test]
+    fn guesses_the_kind_without_a_type() {
+        let guild = json!({"id": "1", "name": "Guild"});
+        assert_eq!(channel(json!({"id": "1", "guild": guild})).kind(), ChannelType::GuildText);
+        assert_eq!(channel(json!({"id": "1", "recipients": ["1", "2"]})).kind(), ChannelType::Dm);
+        assert_eq!(
+            channel(json!({"id": "1", "recipients": ["1", "2", "3"]})).kind(),
+            ChannelType::GroupDm
+        );
+        assert_eq!(channel(json!({"id": "1"})).kind(), ChannelType::Other);
+        let thread = channel(json!({"id": "1", "type": 11, "guild": guild}));
+        assert_eq!(thread.kind(), ChannelType::Thread);
+    }
+}
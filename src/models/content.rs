@@ -0,0 +1,47 @@
+use serde::{Deserialize, Serialize};
+
+/// What kind of messages the account sends, classified from their contents.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[non_exhaustive]
+pub struct ContentStats {
+    pub questions: ContentShare,
+    pub links: ContentShare,
+    /// Messages with at least one fenced code block.
+    pub code: ContentShare,
+    /// Messages starting with a slash or prefix bot command, such as `/roll` or `!play`.
+    pub commands: ContentShare,
+    /// Messages sent to a bot: in a DM with it, or mentioning it. Packages don't record which
+    /// message a reply points to, so replies that don't mention the bot are missed, and bots
+    /// are only known from the relationships and the applications of the entitlements.
+    pub replies_to_bots: ContentShare,
+    /// Messages written entirely in capitals.
+    pub shouting: ContentShare,
+    /// Fenced code blocks per declared language, most used first. Blocks without a
+    /// language are counted under `plain`.
+    pub code_languages: Vec<CodeLanguage>,
+    /// Most used commands first.
+    pub top_commands: Vec<CommandUse>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[non_exhaustive]
+pub struct ContentShare {
+    pub message_count: usize,
+    /// Fraction of all messages.
+    pub share: f64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[non_exhaustive]
+pub struct CodeLanguage {
+    pub language: String,
+    pub block_count: usize,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[non_exhaustive]
+pub struct CommandUse {
+    /// The command with its prefix, e.g. `!play`.
+    pub command: String,
+    pub count: usize,
+}
@@ -0,0 +1,103 @@
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+use super::payment::Payment;
+
+/// Differences between an older and a newer package of the same account.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[non_exhaustive]
+pub struct PackageDiff {
+    pub new_guilds: Vec<GuildChange>,
+    pub removed_guilds: Vec<GuildChange>,
+    pub new_dms: Vec<DMChange>,
+    pub channel_growth: Vec<ChannelGrowth>,
+    pub new_payments: Vec<Payment>,
+    pub account_changes: Vec<FieldChange>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[non_exhaustive]
+pub struct GuildChange {
+    pub id: String,
+    pub name: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[non_exhaustive]
+pub struct DMChange {
+    pub channel_id: String,
+    pub dm_user_id: String,
+    pub message_count: usize,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[non_exhaustive]
+pub struct ChannelGrowth {
+    pub channel_id: String,
+    pub name: String,
+    pub old_count: usize,
+    pub new_count: usize,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[non_exhaustive]
+pub struct FieldChange {
+    pub field: String,
+    pub old_value: Option<String>,
+    pub new_value: Option<String>,
+}
+
+impl fmt::Display for PackageDiff {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "Guilds: +{} / -{}", self.new_guilds.len(), self.removed_guilds.len())?;
+        for guild in &self.new_guilds {
+            writeln!(f, "  + {} ({})", guild.name, guild.id)?;
+        }
+        for guild in &self.removed_guilds {
+            writeln!(f, "  - {} ({})", guild.name, guild.id)?;
+        }
+
+        writeln!(f, "New DMs: {}", self.new_dms.len())?;
+        for dm in &self.new_dms {
+            writeln!(f, "  + {} ({} messages)", dm.dm_user_id, dm.message_count)?;
+        }
+
+        writeln!(f, "Channels with new messages: {}", self.channel_growth.len())?;
+        for growth in &self.channel_growth {
+            writeln!(
+                f,
+                "  {}: {} -> {} ({:+})",
+                growth.name,
+                growth.old_count,
+                growth.new_count,
+                growth.new_count as i64 - growth.old_count as i64
+            )?;
+        }
+
+        writeln!(f, "New payments: {}", self.new_payments.len())?;
+        for payment in &self.new_payments {
+            writeln!(
+                f,
+                "  + {} {} ({} {:.2}, {})",
+                payment.created_at.format("%Y-%m-%d"),
+                payment.description,
+                payment.currency.to_uppercase(),
+                payment.amount as f64 / 100.0,
+                payment.status
+            )?;
+        }
+
+        writeln!(f, "Account changes: {}", self.account_changes.len())?;
+        for change in &self.account_changes {
+            writeln!(
+                f,
+                "  {}: {} -> {}",
+                change.field,
+                change.old_value.as_deref().unwrap_or("(none)"),
+                change.new_value.as_deref().unwrap_or("(none)")
+            )?;
+        }
+
+        Ok(())
+    }
+}
@@ -1,22 +1,75 @@
+use chrono::Offset;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+
+use super::activity::ActivityStats;
+use super::analytics::AnalyticsEvent;
+use super::sentiment::SentimentStats;
 use super::user::User;
-use super::channel::{TopChannel, TopDM};
+use super::voice::VoiceStats;
+use super::channel::{ChannelTypeStats, ParsedChannel, TopChannel, TopDM, TopGroupDM};
+use super::content::ContentStats;
+use super::language::LanguageStats;
+use super::length::LengthStats;
 use super::message::FavoriteWord;
 use super::payment::PaymentInfo;
+use super::words::WordTrends;
+use super::wrapped::YearSummary;
 
-#[derive(Debug, Clone)]
+/// Everything extracted from a package: raw channels plus the statistics built from them.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[non_exhaustive]
 pub struct ExtractedData {
     pub user: Option<User>,
+    pub account_created_at: Option<chrono::DateTime<chrono::Utc>>,
+    /// Set by [`crate::redact::redact`]; the account's own id is a pseudonym too.
+    #[serde(default)]
+    pub redacted: bool,
+    #[serde(skip)]
+    pub channels: Vec<ParsedChannel>,
+    pub guilds: HashMap<String, String>,
     pub top_dms: Vec<TopDM>,
+    pub top_group_dms: Vec<TopGroupDM>,
     pub top_channels: Vec<TopChannel>,
     pub guild_count: usize,
+    pub friend_count: usize,
+    pub blocked_count: usize,
+    /// Services linked to the account, such as `github` or `spotify`.
+    pub linked_services: Vec<String>,
     pub dm_channel_count: usize,
+    pub group_dm_count: usize,
+    /// Every channel that isn't a DM or group DM, including threads and left guilds.
     pub channel_count: usize,
+    /// Channel and message counts for each channel type found in the package.
+    pub channel_types: Vec<ChannelTypeStats>,
     pub message_count: usize,
     pub character_count: usize,
-    pub total_spent: f64,
+    /// Net spending in `spent_currency`, converted at the rate of each payment's month.
+    pub total_spent: Option<f64>,
+    pub spent_currency: Option<String>,
+    /// Offset from UTC, in minutes, that most messages were recorded with. Messages are
+    /// bucketed by their own offset; analytics events, which carry none, use this one.
+    pub utc_offset_minutes: i32,
     pub hours_values: Vec<usize>,
+    pub daily_values: BTreeMap<chrono::NaiveDate, usize>,
     pub favorite_words: Vec<FavoriteWord>,
+    pub word_trends: WordTrends,
+    pub languages: LanguageStats,
+    pub sentiment: SentimentStats,
+    pub content: ContentStats,
+    pub lengths: LengthStats,
+    pub activity: ActivityStats,
+    /// One summary per calendar year with messages, oldest first.
+    pub years: Vec<YearSummary>,
     pub payments: PaymentInfo,
+    /// Counts of the analytics events the statistics use, per day. Days with only other
+    /// events are present with no counts, so the first and last key span the whole log.
+    pub analytics_daily_counts: BTreeMap<chrono::NaiveDate, BTreeMap<String, usize>>,
+    /// Voice channel and call events from the analytics logs; every other event is only
+    /// counted while streaming.
+    #[serde(skip)]
+    pub voice_events: Vec<AnalyticsEvent>,
+    pub voice: VoiceStats,
     pub open_count: Option<usize>,
     pub average_open_count_per_day: Option<usize>,
     pub notification_count: Option<usize>,
@@ -33,20 +86,40 @@ impl Default for ExtractedData {
     fn default() -> Self {
         Self {
             user: None,
+            account_created_at: None,
+            redacted: false,
+            channels: Vec::new(),
+            guilds: HashMap::new(),
             top_dms: Vec::new(),
+            top_group_dms: Vec::new(),
             top_channels: Vec::new(),
             guild_count: 0,
+            friend_count: 0,
+            blocked_count: 0,
+            linked_services: Vec::new(),
             dm_channel_count: 0,
+            group_dm_count: 0,
             channel_count: 0,
+            channel_types: Vec::new(),
             message_count: 0,
             character_count: 0,
-            total_spent: 0.0,
+            total_spent: None,
+            spent_currency: None,
+            utc_offset_minutes: 0,
             hours_values: vec![0; 24],
+            daily_values: BTreeMap::new(),
             favorite_words: Vec::new(),
-            payments: PaymentInfo {
-                total: std::collections::HashMap::new(),
-                list: String::new(),
-            },
+            word_trends: WordTrends::default(),
+            languages: LanguageStats::default(),
+            sentiment: SentimentStats::default(),
+            content: ContentStats::default(),
+            lengths: LengthStats::default(),
+            activity: ActivityStats::default(),
+            years: Vec::new(),
+            payments: PaymentInfo::default(),
+            analytics_daily_counts: BTreeMap::new(),
+            voice_events: Vec::new(),
+            voice: VoiceStats::default(),
             open_count: None,
             average_open_count_per_day: None,
             notification_count: None,
@@ -61,3 +134,10 @@ impl Default for ExtractedData {
     }
 }
 
+impl ExtractedData {
+    /// [`ExtractedData::utc_offset_minutes`] as an offset.
+    pub fn utc_offset(&self) -> chrono::FixedOffset {
+        chrono::FixedOffset::east_opt(self.utc_offset_minutes * 60)
+            .unwrap_or_else(|| chrono::Utc.fix())
+    }
+}
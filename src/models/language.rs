@@ -0,0 +1,45 @@
+use serde::{Deserialize, Serialize};
+
+use super::message::FavoriteWord;
+
+/// Languages detected in message contents, most used first.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[non_exhaustive]
+pub struct LanguageStats {
+    pub languages: Vec<LanguageShare>,
+    /// The busiest channels and the languages used in each.
+    pub channels: Vec<ChannelLanguages>,
+    /// Messages too short or mixed to tell, such as "lol" or a lone link.
+    pub undetected_count: usize,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[non_exhaustive]
+pub struct LanguageShare {
+    /// ISO 639-3 code, e.g. `eng`.
+    pub code: String,
+    pub name: String,
+    pub message_count: usize,
+    /// Fraction of the messages with a detected language.
+    pub share: f64,
+    /// Favorite words in this language with its stopwords left out.
+    pub favorite_words: Vec<FavoriteWord>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[non_exhaustive]
+pub struct ChannelLanguages {
+    pub channel_id: String,
+    pub name: String,
+    pub message_count: usize,
+    pub languages: Vec<LanguageCount>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[non_exhaustive]
+pub struct LanguageCount {
+    pub code: String,
+    pub message_count: usize,
+    /// Fraction of the channel's messages with a detected language.
+    pub share: f64,
+}
@@ -0,0 +1,37 @@
+use serde::{Deserialize, Serialize};
+
+use super::channel::ChannelType;
+
+/// How long messages are, in Unicode characters.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[non_exhaustive]
+pub struct LengthStats {
+    pub average: f64,
+    pub median: f64,
+    pub p95: f64,
+    pub histogram: Vec<LengthBucket>,
+    /// Guild channels, threads and channels of left guilds, most messages first.
+    pub channels: Vec<ChannelLength>,
+    /// DMs and group DMs, most messages first.
+    pub dms: Vec<ChannelLength>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[non_exhaustive]
+pub struct LengthBucket {
+    pub min: usize,
+    /// Inclusive; `None` for the last, open-ended bucket.
+    pub max: Option<usize>,
+    pub message_count: usize,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[non_exhaustive]
+pub struct ChannelLength {
+    pub channel_id: String,
+    pub name: String,
+    pub channel_type: ChannelType,
+    pub message_count: usize,
+    pub average: f64,
+    pub median: f64,
+}
@@ -0,0 +1,53 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+/// What each package contributed to a merged result, in the order they were merged.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[non_exhaustive]
+pub struct MergeReport {
+    pub user_id: String,
+    pub packages: Vec<PackageContribution>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[non_exhaustive]
+pub struct PackageContribution {
+    pub source: String,
+    /// Newest message or analytics event in the package, which sets the merge order.
+    pub package_date: Option<DateTime<Utc>>,
+    pub channel_count: usize,
+    pub message_count: usize,
+    pub new_channels: usize,
+    pub new_messages: usize,
+    pub duplicate_messages: usize,
+    pub new_guilds: usize,
+    pub new_payments: usize,
+}
+
+impl fmt::Display for MergeReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "Merged {} packages of user {}", self.packages.len(), self.user_id)?;
+        for package in &self.packages {
+            write!(
+                f,
+                "{}: {} messages in {} channels",
+                package.source, package.message_count, package.channel_count
+            )?;
+            match package.package_date {
+                Some(date) => writeln!(f, ", up to {}", date.format("%Y-%m-%d"))?,
+                None => writeln!(f)?,
+            }
+            writeln!(
+                f,
+                "  {} new messages, {} duplicates, {} new channels, {} new guilds, {} new payments",
+                package.new_messages,
+                package.duplicate_messages,
+                package.new_channels,
+                package.new_guilds,
+                package.new_payments
+            )?;
+        }
+        Ok(())
+    }
+}
@@ -1,6 +1,11 @@
+use chrono::{DateTime, FixedOffset, NaiveDate, Utc};
 use serde::{Deserialize, Serialize};
 
+use crate::snowflake::Snowflake;
+
+/// A message as stored in the package, either from JSON or the older CSV format.
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[non_exhaustive]
 pub struct Message {
     #[serde(rename = "ID")]
     pub id: u64,
@@ -12,17 +17,86 @@ pub struct Message {
     pub attachments: Option<String>,
 }
 
+/// A sent message with its contents split into words.
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[non_exhaustive]
 pub struct ParsedMessage {
     pub id: u64,
     pub timestamp: String,
+    pub contents: String,
+    pub attachments: Vec<String>,
+    /// Length of the contents in Unicode characters.
     pub length: usize,
     pub words: Vec<String>,
 }
 
-#[derive(Debug, Clone)]
+impl ParsedMessage {
+    /// When the message was sent, read from the id when the package timestamp isn't RFC 3339.
+    pub fn sent_at(&self) -> Option<DateTime<Utc>> {
+        DateTime::parse_from_rfc3339(&self.timestamp)
+            .map(|dt| dt.with_timezone(&Utc))
+            .ok()
+            .or_else(|| Snowflake::new(self.id).map(Snowflake::created_at))
+    }
+
+    /// When the message was sent, in the offset the package recorded it with. Ids carry no
+    /// offset, so the fallback is in UTC.
+    pub fn local_time(&self) -> Option<DateTime<FixedOffset>> {
+        DateTime::parse_from_rfc3339(&self.timestamp)
+            .ok()
+            .or_else(|| Snowflake::new(self.id).map(|id| id.created_at().fixed_offset()))
+    }
+
+    /// The day the message was sent on, in the same offset as [`ParsedMessage::local_time`].
+    /// Days, months and years are all counted by this date.
+    pub fn local_date(&self) -> Option<NaiveDate> {
+        self.local_time().map(|t| t.date_naive())
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[non_exhaustive]
 pub struct FavoriteWord {
     pub word: String,
     pub count: usize,
 }
 
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn message(id: u64, timestamp: &str) -> ParsedMessage {
+        ParsedMessage {
+            id,
+            timestamp: timestamp.to_string(),
+            contents: String::new(),
+            attachments: Vec::new(),
+            length: 0,
+            words: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn keeps_the_recorded_offset() {
+        let message = message(175928847299117063, "2023-05-01T23:30:00+02:00");
+        assert_eq!(
+            message.local_time().unwrap().to_rfc3339(),
+            "2023-05-01T23:30:00+02:00"
+        );
+        assert_eq!(
+            message.sent_at().unwrap().to_rfc3339(),
+            "2023-05-01T21:30:00+00:00"
+        );
+    }
+
+    #[test]
+    fn falls_back_to_the_id() {
+        let csv = message(175928847299117063, "01/05/2023 23:30");
+        assert_eq!(
+            csv.sent_at().unwrap().to_rfc3339(),
+            "2016-04-30T11:18:25.796+00:00"
+        );
+        assert_eq!(csv.local_time().unwrap().offset().local_minus_utc(), 0);
+        assert_eq!(message(1, "garbage").sent_at(), None);
+    }
+}
@@ -1,12 +1,40 @@
+pub mod account;
+pub mod activity;
+pub mod analytics;
+pub mod avatar;
 pub mod user;
 pub mod payment;
 pub mod channel;
+pub mod content;
 pub mod message;
 pub mod extracted_data;
+pub mod merge;
+pub mod diff;
+pub mod language;
+pub mod length;
+pub mod sentiment;
+pub mod voice;
+pub mod wrapped;
+pub mod words;
 
+pub use account::{
+    Connection, Entitlement, GiftCode, GuildSettings, Relationship, RelationshipKind,
+};
+pub use activity::*;
+pub use analytics::*;
+pub use avatar::{default_avatar_index, default_avatar_svg, detect_image_mime};
 pub use user::*;
 pub use payment::*;
 pub use channel::*;
+pub use content::*;
 pub use message::*;
 pub use extracted_data::*;
+pub use merge::*;
+pub use diff::*;
+pub use language::*;
+pub use length::*;
+pub use sentiment::*;
+pub use voice::*;
+pub use wrapped::*;
+pub use words::*;
 
@@ -1,18 +1,288 @@
-use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use std::collections::{BTreeMap, HashMap};
 
+use super::account::null_as_default;
+use crate::snowflake;
+
+/// A payment as listed in `user.json`. Amounts are in minor units (cents).
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[non_exhaustive]
 pub struct Payment {
-    pub status: i32,
+    #[serde(default)]
+    pub id: String,
+    pub status: PaymentStatus,
     pub currency: String,
     pub amount: i64,
-    pub created_at: String,
+    #[serde(default)]
+    pub amount_refunded: i64,
+    pub created_at: DateTime<Utc>,
+    #[serde(default)]
+    pub sku_id: Option<String>,
+    #[serde(default, deserialize_with = "null_as_default")]
     pub description: String,
 }
 
-#[derive(Debug, Clone)]
+impl Payment {
+    /// What the payment actually cost, in minor units: the amount minus any refund for
+    /// completed payments, zero for everything else.
+    pub fn net_amount(&self) -> i64 {
+        match self.status {
+            PaymentStatus::Completed => self.amount - self.amount_refunded,
+            _ => 0,
+        }
+    }
+
+    /// Whether any of the money came back, either through a refund or a chargeback.
+    pub fn is_refunded(&self) -> bool {
+        matches!(self.status, PaymentStatus::Refunded | PaymentStatus::Reversed)
+            || self.amount_refunded > 0
+    }
+
+    /// Whether this pays for a Nitro plan (including Nitro Basic and Classic).
+    pub fn is_nitro(&self) -> bool {
+        self.description.to_lowercase().contains("nitro")
+    }
+}
+
+/// Reads the payment list of `user.json` one payment at a time, so a payment that can't be
+/// read is skipped instead of failing the whole account.
+pub(crate) fn lenient_payments<'de, D>(deserializer: D) -> Result<Vec<Payment>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let values: Vec<serde_json::Value> = null_as_default(deserializer)?;
+    Ok(values
+        .into_iter()
+        .filter_map(|mut value| {
+            normalize_created_at(&mut value);
+            match serde_json::from_value::<Payment>(value) {
+                Ok(payment) => Some(payment),
+                Err(e) => {
+                    eprintln!("[debug] Skipping unreadable payment: {}", e);
+                    None
+                }
+            }
+        })
+        .collect())
+}
+
+/// Rewrites `created_at` as RFC 3339. Timestamps without an offset are taken as UTC, and
+/// missing or unreadable ones fall back to the date encoded in the payment id.
+fn normalize_created_at(value: &mut serde_json::Value) {
+    let Some(payment) = value.as_object_mut() else {
+        return;
+    };
+    let parsed = payment
+        .get("created_at")
+        .and_then(|v| v.as_str())
+        .and_then(parse_timestamp)
+        .or_else(|| {
+            payment
+                .get("id")
+                .and_then(|id| id.as_str())
+                .and_then(snowflake::created_at)
+        });
+    if let Some(created_at) = parsed {
+        payment.insert("created_at".to_string(), created_at.to_rfc3339().into());
+    }
+}
+
+fn parse_timestamp(text: &str) -> Option<DateTime<Utc>> {
+    let text = text.trim();
+    DateTime::parse_from_rfc3339(text)
+        .map(|dt| dt.with_timezone(&Utc))
+        .ok()
+        .or_else(|| {
+            ["%Y-%m-%dT%H:%M:%S%.f", "%Y-%m-%d %H:%M:%S%.f"]
+                .iter()
+                .find_map(|format| chrono::NaiveDateTime::parse_from_str(text, format).ok())
+                .map(|naive| naive.and_utc())
+        })
+}
+
+/// Discord's payment status codes.
+///
+/// Serialized by name; codes Discord adds later are kept as [`PaymentStatus::Unknown`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum PaymentStatus {
+    Pending,
+    Completed,
+    Failed,
+    Reversed,
+    Refunded,
+    Canceled,
+    Unknown(i32),
+}
+
+impl PaymentStatus {
+    pub fn from_code(code: i32) -> Self {
+        match code {
+            0 => PaymentStatus::Pending,
+            1 => PaymentStatus::Completed,
+            2 => PaymentStatus::Failed,
+            3 => PaymentStatus::Reversed,
+            4 => PaymentStatus::Refunded,
+            5 => PaymentStatus::Canceled,
+            code => PaymentStatus::Unknown(code),
+        }
+    }
+
+    pub fn code(self) -> i32 {
+        match self {
+            PaymentStatus::Pending => 0,
+            PaymentStatus::Completed => 1,
+            PaymentStatus::Failed => 2,
+            PaymentStatus::Reversed => 3,
+            PaymentStatus::Refunded => 4,
+            PaymentStatus::Canceled => 5,
+            PaymentStatus::Unknown(code) => code,
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            PaymentStatus::Pending => "pending",
+            PaymentStatus::Completed => "completed",
+            PaymentStatus::Failed => "failed",
+            PaymentStatus::Reversed => "reversed",
+            PaymentStatus::Refunded => "refunded",
+            PaymentStatus::Canceled => "canceled",
+            PaymentStatus::Unknown(_) => "unknown",
+        }
+    }
+}
+
+impl std::fmt::Display for PaymentStatus {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            PaymentStatus::Unknown(code) => write!(f, "unknown ({})", code),
+            status => f.write_str(status.name()),
+        }
+    }
+}
+
+impl Serialize for PaymentStatus {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        match self {
+            PaymentStatus::Unknown(code) => serializer.serialize_i32(*code),
+            status => serializer.serialize_str(status.name()),
+        }
+    }
+}
+
+// Packages carry the numeric code, the cache and API carry the name.
+impl<'de> Deserialize<'de> for PaymentStatus {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        #[derive(Deserialize)]
+        #[serde(untagged)]
+        enum Raw {
+            Code(i32),
+            Name(String),
+        }
+
+        match Raw::deserialize(deserializer)? {
+            Raw::Code(code) => Ok(PaymentStatus::from_code(code)),
+            Raw::Name(name) => (0..=5)
+                .map(PaymentStatus::from_code)
+                .find(|status| status.name() == name)
+                .ok_or_else(|| serde::de::Error::custom(format!("unknown payment status {:?}", name))),
+        }
+    }
+}
+
+/// A stretch of time covered by back-to-back payments for the same Nitro plan.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[non_exhaustive]
+pub struct SubscriptionPeriod {
+    pub description: String,
+    pub sku_id: Option<String>,
+    pub start: DateTime<Utc>,
+    /// When the last payment in the period runs out.
+    pub end: DateTime<Utc>,
+    pub payment_count: usize,
+}
+
+/// Payments summarised for display. Amounts are in major units (dollars, euros, ...).
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[non_exhaustive]
 pub struct PaymentInfo {
+    /// Net spending per currency, after refunds.
     pub total: HashMap<String, f64>,
-    pub list: String,
+    /// Net spending per year and currency.
+    pub by_year: BTreeMap<i32, HashMap<String, f64>>,
+    /// Money returned per currency through refunds and chargebacks.
+    pub refunded: HashMap<String, f64>,
+    pub completed_count: usize,
+    pub refunded_count: usize,
+    pub failed_count: usize,
+    pub subscriptions: Vec<SubscriptionPeriod>,
+    /// Net spending per year, converted into `ExtractedData::spent_currency`.
+    pub spent_by_year: BTreeMap<i32, f64>,
+    /// Currencies left out of the converted totals for lack of an exchange rate.
+    pub unconverted_currencies: Vec<String>,
 }
 
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[derive(Deserialize)]
+    struct Payments {
+        #[serde(deserialize_with = "lenient_payments")]
+        payments: Vec<Payment>,
+    }
+
+    fn payment(status: i32, amount: i64, amount_refunded: i64) -> Payment {
+        serde_json::from_value(json!({
+            "id": "1", "status": status, "currency": "usd", "amount": amount,
+            "amount_refunded": amount_refunded, "created_at": "2023-01-05T10:00:00Z",
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn status_codes_round_trip() {
+        for code in 0..=6 {
+            let status = PaymentStatus::from_code(code);
+            assert_eq!(status.code(), code);
+            let json = serde_json::to_value(status).unwrap();
+            assert_eq!(serde_json::from_value::<PaymentStatus>(json).unwrap(), status);
+        }
+        assert_eq!(PaymentStatus::from_code(1).to_string(), "completed");
+        assert_eq!(PaymentStatus::from_code(9).to_string(), "unknown (9)");
+        assert!(serde_json::from_value::<PaymentStatus>(json!("lost")).is_err());
+    }
+
+    #[test]
+    fn net_amount_counts_completed_payments_only() {
+        assert_eq!(payment(1, 999, 0).net_amount(), 999);
+        assert_eq!(payment(1, 999, 500).net_amount(), 499);
+        assert_eq!(payment(2, 999, 0).net_amount(), 0);
+        assert!(payment(1, 999, 500).is_refunded());
+        assert!(payment(3, 999, 0).is_refunded());
+        assert!(!payment(1, 999, 0).is_refunded());
+    }
+
+    #[test]
+    fn skips_only_unreadable_payments() {
+        let user: Payments = serde_json::from_value(json!({"payments": [
+            {"id": "1", "status": 1, "currency": "usd", "amount": 999,
+                "created_at": "2023-01-05T10:00:00.000000+00:00", "description": null},
+            {"id": "2", "status": 1, "currency": "usd", "amount": 499,
+                "created_at": "2023-02-05 10:00:00"},
+            {"id": "175928847299117063", "status": 1, "currency": "usd", "amount": 299},
+            {"id": "4", "status": 1, "currency": "usd"},
+            "garbage",
+        ]}))
+        .unwrap();
+
+        let payments = &user.payments;
+        assert_eq!(payments.len(), 3);
+        assert_eq!(payments[0].description, "");
+        assert_eq!(payments[1].created_at.to_rfc3339(), "2023-02-05T10:00:00+00:00");
+        assert_eq!(payments[2].created_at.to_rfc3339(), "2016-04-30T11:18:25.796+00:00");
+    }
+}
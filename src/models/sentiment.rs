@@ -0,0 +1,39 @@
+use chrono::NaiveDate;
+use serde::{Deserialize, Serialize};
+
+use super::channel::ChannelType;
+
+/// Average message sentiment from -1 (negative) to 1 (positive). Only messages with at least
+/// one word from the lexicon are scored.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[non_exhaustive]
+pub struct SentimentStats {
+    pub scored_message_count: usize,
+    pub average: f64,
+    /// Guild channels, threads and channels of left guilds, most scored messages first.
+    pub channels: Vec<ChannelSentiment>,
+    /// DMs and group DMs, most scored messages first.
+    pub dms: Vec<ChannelSentiment>,
+    /// Keyed by the first day of each month, oldest first.
+    pub months: Vec<DateSentiment>,
+    pub most_positive_days: Vec<DateSentiment>,
+    pub most_negative_days: Vec<DateSentiment>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[non_exhaustive]
+pub struct ChannelSentiment {
+    pub channel_id: String,
+    pub name: String,
+    pub channel_type: ChannelType,
+    pub scored_message_count: usize,
+    pub average: f64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[non_exhaustive]
+pub struct DateSentiment {
+    pub date: NaiveDate,
+    pub scored_message_count: usize,
+    pub average: f64,
+}
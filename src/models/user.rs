@@ -1,19 +1,124 @@
+use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
 
+use crate::snowflake;
+
+use super::account::{
+    Connection, Entitlement, GiftCode, GuildSettings, Relationship, RelationshipKind,
+    lenient_discriminator, null_as_default,
+};
+
+/// The account that requested the package, from `user.json`.
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[non_exhaustive]
 pub struct User {
     pub id: String,
     pub username: String,
+    #[serde(deserialize_with = "lenient_discriminator")]
     pub discriminator: u16,
+    #[serde(default)]
+    pub global_name: Option<String>,
+    /// Avatar hash from `user.json`, set when the account has a custom avatar.
+    #[serde(rename = "avatar", default)]
+    pub avatar_hash: Option<String>,
+    /// Raw bytes of the packaged avatar image. Kept out of JSON, see [`User::avatar_data_uri`].
+    #[serde(skip)]
     pub avatar: Option<Vec<u8>>,
-    pub default_avatar_url: Option<String>,
+    #[serde(default)]
+    pub avatar_extension: Option<String>,
+    /// Which of the six default avatars Discord shows for this account.
+    #[serde(default)]
+    pub default_avatar_index: usize,
+    #[serde(default)]
+    pub email: Option<String>,
+    #[serde(default)]
+    pub phone: Option<String>,
+    #[serde(default)]
+    pub verified: bool,
+    /// Public and internal user flag bits.
+    #[serde(default, deserialize_with = "null_as_default")]
+    pub flags: u64,
+    #[serde(default, deserialize_with = "null_as_default")]
+    pub connections: Vec<Connection>,
+    #[serde(default, deserialize_with = "null_as_default")]
+    pub relationships: Vec<Relationship>,
+    #[serde(default, deserialize_with = "null_as_default")]
+    pub guild_settings: Vec<GuildSettings>,
+    #[serde(default, deserialize_with = "null_as_default")]
+    pub entitlements: Vec<Entitlement>,
+    #[serde(default, deserialize_with = "null_as_default")]
+    pub gift_codes: Vec<GiftCode>,
+    /// Private notes about other users, keyed by their user id.
+    #[serde(default, deserialize_with = "null_as_default")]
+    pub notes: HashMap<String, String>,
+    /// Payments that couldn't be read are left out, see [`super::payment::lenient_payments`].
+    #[serde(default, deserialize_with = "super::payment::lenient_payments")]
     pub payments: Vec<super::payment::Payment>,
 }
 
+impl User {
+    /// When the account was registered.
+    pub fn created_at(&self) -> Option<DateTime<Utc>> {
+        snowflake::created_at(&self.id)
+    }
+
+    pub fn friend_count(&self) -> usize {
+        self.relationship_count(RelationshipKind::Friend)
+    }
+
+    pub fn blocked_count(&self) -> usize {
+        self.relationship_count(RelationshipKind::Blocked)
+    }
+
+    fn relationship_count(&self, kind: RelationshipKind) -> usize {
+        self.relationships.iter().filter(|r| r.kind == kind).count()
+    }
+
+    /// Services with a connection that hasn't been revoked, sorted and deduplicated.
+    pub fn linked_services(&self) -> Vec<String> {
+        let mut services: Vec<String> = self
+            .connections
+            .iter()
+            .filter(|c| !c.revoked)
+            .map(|c| c.service.clone())
+            .collect();
+        services.sort();
+        services.dedup();
+        services
+    }
+}
+
+/// Public profile of another user.
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[non_exhaustive]
 pub struct UserData {
+    #[serde(default)]
+    pub id: String,
     pub username: String,
+    #[serde(default, deserialize_with = "lenient_discriminator")]
     pub discriminator: u16,
+    #[serde(default)]
+    pub global_name: Option<String>,
     pub avatar: Option<String>,
+    #[serde(default)]
+    pub bot: bool,
 }
 
+impl UserData {
+    pub fn created_at(&self) -> Option<DateTime<Utc>> {
+        snowflake::created_at(&self.id)
+    }
+
+    /// Placeholder profile for a user the package only knows by id.
+    pub(crate) fn from_id(id: String) -> Self {
+        Self {
+            username: id.clone(),
+            id,
+            discriminator: 0,
+            global_name: None,
+            avatar: None,
+            bot: false,
+        }
+    }
+}
@@ -0,0 +1,78 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+/// Time spent in voice channels and calls, reconstructed from the analytics join and leave
+/// events.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[non_exhaustive]
+pub struct VoiceStats {
+    pub session_count: usize,
+    pub total_minutes: f64,
+    pub longest_session: Option<VoiceSession>,
+    /// Most minutes first. DM and group DM calls have no guild and aren't listed.
+    pub guilds: Vec<VoiceTime>,
+    /// Voice channels and calls, most minutes first.
+    pub channels: Vec<VoiceTime>,
+    /// Minutes in voice for each hour of the day, at `ExtractedData::utc_offset_minutes`;
+    /// sessions spanning several hours are split between them.
+    pub hours_minutes: Vec<f64>,
+    /// Leave events without a matching join, plus sessions dropped because they never ended
+    /// or were implausibly long.
+    pub unmatched_event_count: usize,
+}
+
+impl Default for VoiceStats {
+    fn default() -> Self {
+        Self {
+            session_count: 0,
+            total_minutes: 0.0,
+            longest_session: None,
+            guilds: Vec::new(),
+            channels: Vec::new(),
+            hours_minutes: vec![0.0; 24],
+            unmatched_event_count: 0,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+#[non_exhaustive]
+pub enum VoiceSessionKind {
+    VoiceChannel,
+    Call,
+}
+
+impl VoiceSessionKind {
+    /// The name used in JSON and exports.
+    pub fn name(self) -> &'static str {
+        match self {
+            VoiceSessionKind::VoiceChannel => "voice_channel",
+            VoiceSessionKind::Call => "call",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[non_exhaustive]
+pub struct VoiceSession {
+    pub kind: VoiceSessionKind,
+    pub channel_id: Option<String>,
+    /// The channel name when the package has messages from it.
+    pub channel_name: Option<String>,
+    pub guild_id: Option<String>,
+    pub guild_name: Option<String>,
+    pub start: DateTime<Utc>,
+    pub end: DateTime<Utc>,
+    pub minutes: f64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[non_exhaustive]
+pub struct VoiceTime {
+    /// Guild or channel id.
+    pub id: String,
+    pub name: Option<String>,
+    pub session_count: usize,
+    pub minutes: f64,
+}
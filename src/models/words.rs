@@ -0,0 +1,48 @@
+use chrono::NaiveDate;
+use serde::{Deserialize, Serialize};
+
+/// Phrase frequencies and the words whose use jumped in a single month.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[non_exhaustive]
+pub struct WordTrends {
+    pub bigrams: Vec<Phrase>,
+    pub trigrams: Vec<Phrase>,
+    /// Strongest spike first.
+    pub spikes: Vec<WordSpike>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[non_exhaustive]
+pub struct Phrase {
+    pub phrase: String,
+    pub count: usize,
+}
+
+/// A month in which a word was used far more often than in the other months.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[non_exhaustive]
+pub struct WordSpike {
+    pub word: String,
+    /// First day of the month.
+    pub month: NaiveDate,
+    pub count: usize,
+    /// Average uses per month over the rest of the active period.
+    pub baseline: f64,
+}
+
+/// Monthly uses of a word or phrase, answered on demand from the messages.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[non_exhaustive]
+pub struct WordTrend {
+    pub query: String,
+    pub total: usize,
+    /// Every month from the first to the last message, including months without a match.
+    pub months: Vec<MonthCount>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[non_exhaustive]
+pub struct MonthCount {
+    pub month: NaiveDate,
+    pub count: usize,
+}
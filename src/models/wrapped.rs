@@ -0,0 +1,116 @@
+use chrono::{DateTime, FixedOffset};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fmt;
+
+use super::channel::{TopChannel, TopDM, TopGroupDM};
+use super::message::FavoriteWord;
+
+/// One calendar year of activity, the "wrapped" view of a package.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[non_exhaustive]
+pub struct YearSummary {
+    pub year: i32,
+    pub message_count: usize,
+    pub character_count: usize,
+    pub active_days: usize,
+    pub top_channels: Vec<TopChannel>,
+    pub top_dms: Vec<TopDM>,
+    pub top_group_dms: Vec<TopGroupDM>,
+    pub favorite_words: Vec<FavoriteWord>,
+    pub hours_values: Vec<usize>,
+    /// Guilds whose first message from the account falls in this year.
+    pub new_guilds: Vec<NewGuild>,
+    /// Net spending per currency.
+    pub spent_by_currency: HashMap<String, f64>,
+    /// Net spending converted into `ExtractedData::spent_currency`.
+    pub spent: Option<f64>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[non_exhaustive]
+pub struct NewGuild {
+    pub id: String,
+    pub name: String,
+    /// In the offset the message was recorded with, like every date in the summary.
+    pub first_message_at: DateTime<FixedOffset>,
+}
+
+impl fmt::Display for YearSummary {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "{} wrapped", self.year)?;
+        writeln!(
+            f,
+            "{} messages, {} characters, active on {} days",
+            self.message_count, self.character_count, self.active_days
+        )?;
+
+        if let Some(peak) = self
+            .hours_values
+            .iter()
+            .enumerate()
+            .max_by_key(|(_, count)| **count)
+            .filter(|(_, count)| **count > 0)
+        {
+            writeln!(f, "Most active hour: {:02}:00", peak.0)?;
+        }
+
+        writeln!(f, "Top channels:")?;
+        for channel in &self.top_channels {
+            match &channel.guild_name {
+                Some(guild) => writeln!(
+                    f,
+                    "  {} ({}): {}",
+                    channel.name, guild, channel.message_count
+                )?,
+                None => writeln!(f, "  {}: {}", channel.name, channel.message_count)?,
+            }
+        }
+
+        writeln!(f, "Top DMs:")?;
+        for dm in &self.top_dms {
+            let name = dm
+                .user_data
+                .as_ref()
+                .map_or(dm.dm_user_id.as_str(), |u| u.username.as_str());
+            writeln!(f, "  {}: {}", name, dm.message_count)?;
+        }
+
+        if !self.top_group_dms.is_empty() {
+            writeln!(f, "Top group DMs:")?;
+            for group in &self.top_group_dms {
+                writeln!(f, "  {}: {}", group.name, group.message_count)?;
+            }
+        }
+
+        let words: Vec<&str> = self
+            .favorite_words
+            .iter()
+            .map(|w| w.word.as_str())
+            .collect();
+        writeln!(f, "Favorite words: {}", words.join(", "))?;
+
+        if !self.new_guilds.is_empty() {
+            writeln!(f, "New guilds:")?;
+            for guild in &self.new_guilds {
+                writeln!(
+                    f,
+                    "  {} (first message {})",
+                    guild.name,
+                    guild.first_message_at.format("%Y-%m-%d")
+                )?;
+            }
+        }
+
+        let mut spent: Vec<_> = self.spent_by_currency.iter().collect();
+        spent.sort_by(|a, b| a.0.cmp(b.0));
+        for (currency, amount) in spent
+            .into_iter()
+            .filter(|(_, amount)| amount.abs() >= 0.005)
+        {
+            writeln!(f, "Spent {:.2} {}", amount, currency.to_uppercase())?;
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,353 @@
+use chrono::{DateTime, Duration, NaiveDate, Utc};
+use std::cmp::Reverse;
+use std::collections::HashMap;
+
+use crate::models::{
+    ActivityStats, BusiestDay, ChannelSessions, DayRange, ExtractedData, MessageHighlight,
+    ParsedChannel, ParsedMessage, Session,
+};
+use crate::parser::Parser;
+use crate::parser::utils::median;
+
+impl Parser {
+    /// Streaks, gaps, the busiest day and sessions. Expects `daily_values` to be filled in.
+    pub(super) fn compute_activity(&self, extracted_data: &mut ExtractedData) {
+        let session_gap = Duration::minutes(self.options.session_gap_minutes);
+        let mut activity = ActivityStats::default();
+
+        let days: Vec<NaiveDate> = extracted_data.daily_values.keys().copied().collect();
+        activity.longest_streak = Parser::longest_streak(&days);
+        activity.longest_gap = Parser::longest_gap(&days);
+
+        let mut first: Option<(&ParsedChannel, &ParsedMessage, DateTime<Utc>)> = None;
+        let mut last: Option<(&ParsedChannel, &ParsedMessage, DateTime<Utc>)> = None;
+        // Keyed by channel id, since names like "general" repeat across guilds.
+        let mut day_channels: HashMap<NaiveDate, HashMap<&str, usize>> = HashMap::new();
+        let channel_names: HashMap<&str, &str> = extracted_data
+            .channels
+            .iter()
+            .map(|p| (p.channel.id.as_str(), p.name.as_str()))
+            .collect();
+        let mut all_times: Vec<DateTime<Utc>> = Vec::new();
+
+        for parsed in &extracted_data.channels {
+            let mut times: Vec<DateTime<Utc>> = Vec::with_capacity(parsed.messages.len());
+            for message in &parsed.messages {
+                let (Some(sent_at), Some(date)) = (message.sent_at(), message.local_date()) else {
+                    continue;
+                };
+                if first.is_none_or(|(_, _, t)| sent_at < t) {
+                    first = Some((parsed, message, sent_at));
+                }
+                if last.is_none_or(|(_, _, t)| sent_at > t) {
+                    last = Some((parsed, message, sent_at));
+                }
+                *day_channels
+                    .entry(date)
+                    .or_default()
+                    .entry(parsed.channel.id.as_str())
+                    .or_insert(0) += 1;
+                times.push(sent_at);
+            }
+            times.sort();
+
+            let sessions = Parser::split_sessions(&times, session_gap);
+            if !sessions.is_empty() {
+                activity.channel_sessions.push(ChannelSessions {
+                    channel_id: parsed.channel.id.clone(),
+                    name: parsed.name.clone(),
+                    session_count: sessions.len(),
+                    median_session_minutes: median(sessions.iter().map(Session::minutes).collect()),
+                    median_session_messages: median(
+                        sessions.iter().map(|s| s.message_count as f64).collect(),
+                    ),
+                });
+            }
+            all_times.extend(times);
+        }
+
+        let highlight =
+            |(parsed, message, sent_at): (&ParsedChannel, &ParsedMessage, DateTime<Utc>)| {
+                MessageHighlight {
+                    channel_id: parsed.channel.id.clone(),
+                    channel_name: parsed.name.clone(),
+                    sent_at,
+                    contents: message.contents.clone(),
+                }
+            };
+        activity.first_message = first.map(highlight);
+        activity.last_message = last.map(highlight);
+
+        activity.busiest_day = extracted_data
+            .daily_values
+            .iter()
+            .max_by_key(|(date, count)| (**count, Reverse(**date)))
+            .and_then(|(date, count)| {
+                let name = |id: &str| channel_names.get(id).copied().unwrap_or_default();
+                let (channel_id, channel_count) = day_channels
+                    .get(date)?
+                    .iter()
+                    .max_by_key(|(id, count)| (**count, Reverse(name(id)), Reverse(**id)))?;
+                Some(BusiestDay {
+                    date: *date,
+                    message_count: *count,
+                    top_channel_id: channel_id.to_string(),
+                    top_channel: name(channel_id).to_string(),
+                    top_channel_message_count: *channel_count,
+                })
+            });
+
+        all_times.sort();
+        let sessions = Parser::split_sessions(&all_times, session_gap);
+        activity.session_count = sessions.len();
+        activity.median_session_minutes = median(sessions.iter().map(Session::minutes).collect());
+        activity.longest_session = sessions
+            .into_iter()
+            .max_by_key(|s| (s.end - s.start, s.message_count));
+
+        activity
+            .channel_sessions
+            .sort_by_key(|c| Reverse(c.session_count));
+        activity.channel_sessions.truncate(self.options.top_count);
+
+        extracted_data.activity = activity;
+    }
+
+    fn split_sessions(times: &[DateTime<Utc>], gap: Duration) -> Vec<Session> {
+        let mut sessions: Vec<Session> = Vec::new();
+        for &time in times {
+            match sessions.last_mut() {
+                Some(session) if time - session.end <= gap => {
+                    session.end = time;
+                    session.message_count += 1;
+                }
+                _ => sessions.push(Session {
+                    start: time,
+                    end: time,
+                    message_count: 1,
+                }),
+            }
+        }
+        sessions
+    }
+
+    fn longest_streak(days: &[NaiveDate]) -> Option<DayRange> {
+        let mut best: Option<DayRange> = None;
+        let mut current: Option<DayRange> = None;
+        for &day in days {
+            current = match current {
+                Some(range) if day.pred_opt() == Some(range.end) => Some(DayRange {
+                    end: day,
+                    days: range.days + 1,
+                    ..range
+                }),
+                _ => Some(DayRange {
+                    start: day,
+                    end: day,
+                    days: 1,
+                }),
+            };
+            if let Some(range) = &current
+                && best.as_ref().is_none_or(|b| range.days > b.days)
+            {
+                best = current.clone();
+            }
+        }
+        best
+    }
+
+    fn longest_gap(days: &[NaiveDate]) -> Option<DayRange> {
+        days.windows(2)
+            .filter_map(|pair| {
+                let start = pair[0].succ_opt()?;
+                let end = pair[1].pred_opt()?;
+                let inactive = (pair[1] - pair[0]).num_days() - 1;
+                (inactive > 0).then_some(DayRange {
+                    start,
+                    end,
+                    days: inactive as usize,
+                })
+            })
+            .max_by_key(|gap| (gap.days, Reverse(gap.start)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::fixtures::{data, guild_channel, message};
+
+    fn day(s: &str) -> NaiveDate {
+        s.parse().unwrap()
+    }
+
+    fn time(s: &str) -> DateTime<Utc> {
+        s.parse().unwrap()
+    }
+
+    #[test]
+    fn longest_streak_prefers_the_earliest_on_ties() {
+        let days = [
+            "2023-01-01",
+            "2023-01-02",
+            "2023-01-05",
+            "2023-01-06",
+            "2023-01-08",
+        ]
+        .map(day);
+        let streak = Parser::longest_streak(&days).unwrap();
+        assert_eq!(
+            (streak.start, streak.end, streak.days),
+            (day("2023-01-01"), day("2023-01-02"), 2)
+        );
+
+        let days = ["2023-01-01", "2023-01-03", "2023-01-04", "2023-01-05"].map(day);
+        let streak = Parser::longest_streak(&days).unwrap();
+        assert_eq!((streak.start, streak.days), (day("2023-01-03"), 3));
+        assert!(Parser::longest_streak(&[]).is_none());
+    }
+
+    #[test]
+    fn longest_gap_counts_inactive_days_only() {
+        let days = ["2023-01-01", "2023-01-02", "2023-01-06", "2023-01-08"].map(day);
+        let gap = Parser::longest_gap(&days).unwrap();
+        assert_eq!(
+            (gap.start, gap.end, gap.days),
+            (day("2023-01-03"), day("2023-01-05"), 3)
+        );
+        assert!(Parser::longest_gap(&["2023-01-01", "2023-01-02"].map(day)).is_none());
+    }
+
+    #[test]
+    fn sessions_split_on_gaps_longer_than_the_limit() {
+        let times = [
+            "2023-01-01T10:00:00Z",
+            "2023-01-01T10:30:00Z",
+            "2023-01-01T11:00:00Z",
+            "2023-01-01T11:31:00Z",
+        ]
+        .map(time);
+        let sessions = Parser::split_sessions(&times, Duration::minutes(30));
+        assert_eq!(sessions.len(), 2);
+        assert_eq!(sessions[0].message_count, 3);
+        assert_eq!(sessions[0].minutes(), 60.0);
+        assert_eq!(sessions[1].start, time("2023-01-01T11:31:00Z"));
+        assert_eq!(sessions[1].message_count, 1);
+    }
+
+    #[test]
+    fn busiest_day_tells_same_named_channels_apart() {
+        let mut data = data(vec![
+            guild_channel(
+                "1",
+                "general",
+                &[("2023-01-02T10:00:00Z", "a"), ("2023-01-02T11:00:00Z", "b")],
+            ),
+            guild_channel(
+                "2",
+                "general",
+                &[
+                    ("2023-01-02T12:00:00Z", "c"),
+                    ("2023-01-02T12:10:00Z", "d"),
+                    ("2023-01-02T12:20:00Z", "e"),
+                ],
+            ),
+            guild_channel("3", "other", &[("2023-01-01T10:00:00Z", "f")]),
+        ]);
+        Parser::new().compute_channel_stats(&mut data);
+
+        let busiest = data.activity.busiest_day.unwrap();
+        assert_eq!(busiest.date, day("2023-01-02"));
+        assert_eq!(busiest.message_count, 5);
+        assert_eq!(busiest.top_channel_id, "2");
+        assert_eq!(busiest.top_channel, "general");
+        assert_eq!(busiest.top_channel_message_count, 3);
+        assert_eq!(data.activity.longest_streak.unwrap().days, 2);
+    }
+
+    #[test]
+    fn empty_package_has_no_activity() {
+        let mut data = data(vec![guild_channel("1", "general", &[])]);
+        Parser::new().compute_channel_stats(&mut data);
+
+        let activity = &data.activity;
+        assert!(activity.longest_streak.is_none());
+        assert!(activity.longest_gap.is_none());
+        assert!(activity.busiest_day.is_none());
+        assert!(activity.first_message.is_none());
+        assert!(activity.longest_session.is_none());
+        assert_eq!(activity.session_count, 0);
+        assert!(activity.channel_sessions.is_empty());
+        assert!(data.daily_values.is_empty());
+    }
+
+    #[test]
+    fn single_message_is_a_one_day_streak() {
+        let mut data = data(vec![guild_channel(
+            "1",
+            "general",
+            &[("2023-01-01T10:00:00Z", "hi")],
+        )]);
+        Parser::new().compute_channel_stats(&mut data);
+
+        let activity = &data.activity;
+        assert_eq!(activity.longest_streak.as_ref().unwrap().days, 1);
+        assert!(activity.longest_gap.is_none());
+        assert_eq!(activity.busiest_day.as_ref().unwrap().message_count, 1);
+        assert_eq!(activity.session_count, 1);
+        assert_eq!(activity.median_session_minutes, 0.0);
+        assert_eq!(
+            activity.first_message.as_ref().unwrap().sent_at,
+            activity.last_message.as_ref().unwrap().sent_at
+        );
+    }
+
+    #[test]
+    fn unparseable_timestamps_fall_back_to_the_id() {
+        let mut channel = guild_channel("1", "general", &[("2023-01-01T10:00:00Z", "hi")]);
+        channel.messages.extend([
+            // The older CSV exports wrote dates like this.
+            message(175928847299117063, "30/04/2016 11:18", "old"),
+            // Ids this small carry no date either, so the message is left out.
+            message(2, "garbage", "lost"),
+        ]);
+        let mut data = data(vec![channel]);
+        Parser::new().compute_channel_stats(&mut data);
+
+        let days: Vec<(NaiveDate, usize)> =
+            data.daily_values.iter().map(|(d, c)| (*d, *c)).collect();
+        assert_eq!(days, [(day("2016-04-30"), 1), (day("2023-01-01"), 1)]);
+        assert_eq!(data.hours_values[11], 1);
+        let first = data.activity.first_message.unwrap();
+        assert_eq!(first.sent_at, time("2016-04-30T11:18:25.796Z"));
+    }
+
+    #[test]
+    fn hours_days_and_years_share_the_local_offset() {
+        // 23:30 on New Year's Eve in New York is already 2023 in UTC.
+        let mut data = data(vec![guild_channel(
+            "1",
+            "general",
+            &[
+                ("2022-12-31T23:30:00-05:00", "late"),
+                ("2022-12-31T23:45:00-05:00", "later"),
+                ("2023-01-01T09:00:00-05:00", "morning"),
+            ],
+        )]);
+        Parser::new().compute_channel_stats(&mut data);
+
+        assert_eq!(data.utc_offset_minutes, -300);
+        assert_eq!(data.hours_values[23], 2);
+        let days: Vec<(NaiveDate, usize)> =
+            data.daily_values.iter().map(|(d, c)| (*d, *c)).collect();
+        assert_eq!(days, [(day("2022-12-31"), 2), (day("2023-01-01"), 1)]);
+        assert_eq!(data.activity.busiest_day.unwrap().date, day("2022-12-31"));
+        let years: Vec<(i32, usize)> = data
+            .years
+            .iter()
+            .map(|y| (y.year, y.message_count))
+            .collect();
+        assert_eq!(years, [(2022, 2), (2023, 1)]);
+        assert_eq!(data.years[0].hours_values[23], 2);
+    }
+}
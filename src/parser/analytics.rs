@@ -1,14 +1,40 @@
 use anyhow::Result;
-use std::io::Read;
+use serde::Deserialize;
+use std::collections::BTreeMap;
+use std::fs::File;
+use std::io::{BufRead, BufReader, Read};
+use std::path::Path;
 use zip::ZipArchive;
 
-use crate::models::ExtractedData;
+use crate::models::{AnalyticsEvent, ExtractedData};
 use crate::parser::Parser;
 
+/// Event types counted per day; every other event is only read to find the active days.
+const COUNTED_EVENTS: &[&str] = &[
+    "app_opened",
+    "notification_clicked",
+    "join_voice_channel",
+    "join_call",
+    "add_reaction",
+    "message_edited",
+    "send_message",
+    "application_command_used",
+];
+
+#[derive(Deserialize)]
+struct RawEvent {
+    event_type: Option<String>,
+    timestamp: Option<String>,
+    channel_id: Option<serde_json::Value>,
+    guild_id: Option<serde_json::Value>,
+}
+
 impl Parser {
+    /// Streams the analytics logs, counting events per day as they are read. Only the voice
+    /// events are kept, for [`Parser::compute_voice_stats`].
     pub(super) fn load_analytics<R: Read + std::io::Seek, F>(
         &self,
-        _archive: &mut ZipArchive<R>,
+        archive: &mut ZipArchive<R>,
         file_names: &[&String],
         extracted_data: &mut ExtractedData,
         progress_callback: &F,
@@ -17,26 +43,208 @@ impl Parser {
         F: Fn(String) + Send + Sync,
     {
         progress_callback("Processing analytics...".to_string());
-        
+
+        let analytics_files = Parser::analytics_files(file_names.iter().map(|f| f.as_str()))?;
+        if analytics_files.is_empty() {
+            return Ok(());
+        }
+
+        let mut daily_counts: BTreeMap<chrono::NaiveDate, BTreeMap<String, usize>> =
+            BTreeMap::new();
+        let mut voice_events = Vec::new();
+        let mut skipped = 0;
+        let offset = extracted_data.utc_offset();
+        for path in analytics_files {
+            eprintln!("[debug] Loading analytics from: {}", path);
+            let Some(&index) = self.file_index.get(path) else {
+                continue;
+            };
+            skipped += Parser::stream_events(archive.by_index(index)?, |event| {
+                let Ok(timestamp) = chrono::DateTime::parse_from_rfc3339(&event.timestamp) else {
+                    return Ok(());
+                };
+                let day = daily_counts
+                    .entry(timestamp.with_timezone(&offset).date_naive())
+                    .or_default();
+                if COUNTED_EVENTS.contains(&event.event_type.as_str()) {
+                    *day.entry(event.event_type.clone()).or_insert(0) += 1;
+                }
+                if Parser::is_voice_event(&event.event_type) {
+                    voice_events.push(event);
+                }
+                Ok(())
+            })?;
+        }
+        if skipped > 0 {
+            eprintln!("[debug] Skipped {} unreadable analytics events", skipped);
+        }
+
+        extracted_data.analytics_daily_counts = daily_counts;
+        extracted_data.voice_events = voice_events;
+        Parser::apply_event_counts(extracted_data);
+        self.compute_voice_stats(extracted_data);
+
+        Ok(())
+    }
+
+    /// Streams every event of the analytics logs in the package at `path` to `on_event`
+    /// without keeping them in memory. Returns how many unreadable lines were skipped.
+    pub fn read_analytics_events<F>(path: &Path, mut on_event: F) -> Result<usize>
+    where
+        F: FnMut(AnalyticsEvent) -> Result<()>,
+    {
+        let mut archive = ZipArchive::new(BufReader::new(File::open(path)?))?;
+        let files: Vec<String> = Parser::analytics_files(archive.file_names())?
+            .into_iter()
+            .map(str::to_string)
+            .collect();
+        let mut skipped = 0;
+        for name in files {
+            eprintln!("[debug] Reading analytics from: {}", name);
+            skipped += Parser::stream_events(archive.by_name(&name)?, &mut on_event)?;
+        }
+        Ok(skipped)
+    }
+
+    /// The analytics log files among `file_names`, in order.
+    fn analytics_files<'a>(file_names: impl Iterator<Item = &'a str>) -> Result<Vec<&'a str>> {
         let analytics_regex =
             regex::Regex::new(r"analytics/events-[0-9]{4}-[0-9]{5}-of-[0-9]{5}\.json$")?;
-        
-        let analytics_file = file_names
-            .iter()
-            .find(|f| analytics_regex.is_match(f.as_str()));
-        
-        if analytics_file.is_some() {
-            // TODO: parse analytics file
-            extracted_data.open_count = Some(0);
-            extracted_data.notification_count = Some(0);
-            extracted_data.join_voice_channel_count = Some(0);
-            extracted_data.join_call_count = Some(0);
-            extracted_data.add_reaction_count = Some(0);
-            extracted_data.message_edited_count = Some(0);
-            extracted_data.sent_message_count = Some(0);
-            extracted_data.slash_command_used_count = Some(0);
+        let mut files: Vec<&str> = file_names.filter(|f| analytics_regex.is_match(f)).collect();
+        files.sort();
+        Ok(files)
+    }
+
+    /// Reads one JSON event per line, skipping lines without an event type or timestamp.
+    fn stream_events<R: Read, F>(reader: R, mut on_event: F) -> Result<usize>
+    where
+        F: FnMut(AnalyticsEvent) -> Result<()>,
+    {
+        let mut skipped = 0;
+        for line in BufReader::new(reader).lines() {
+            let line = line?;
+            if line.trim().is_empty() {
+                continue;
+            }
+            match serde_json::from_str::<RawEvent>(&line) {
+                Ok(RawEvent {
+                    event_type: Some(event_type),
+                    timestamp: Some(timestamp),
+                    channel_id,
+                    guild_id,
+                }) => on_event(AnalyticsEvent {
+                    event_type,
+                    timestamp: timestamp.trim_matches('"').to_string(),
+                    channel_id: channel_id.and_then(Parser::json_id),
+                    guild_id: guild_id.and_then(Parser::json_id),
+                })?,
+                _ => skipped += 1,
+            }
         }
-        
-        Ok(())
+        Ok(skipped)
+    }
+
+    /// Sets the event counters from `analytics_daily_counts`.
+    pub(super) fn apply_event_counts(extracted_data: &mut ExtractedData) {
+        let daily_counts = &extracted_data.analytics_daily_counts;
+        if daily_counts.is_empty() {
+            return;
+        }
+        let count = |event_type: &str| -> usize {
+            daily_counts
+                .values()
+                .filter_map(|counts| counts.get(event_type))
+                .sum()
+        };
+        let days = match (daily_counts.keys().next(), daily_counts.keys().next_back()) {
+            (Some(first), Some(last)) => (*last - *first).num_days() as usize + 1,
+            _ => 1,
+        };
+
+        extracted_data.open_count = Some(count("app_opened"));
+        extracted_data.notification_count = Some(count("notification_clicked"));
+        extracted_data.join_voice_channel_count = Some(count("join_voice_channel"));
+        extracted_data.join_call_count = Some(count("join_call"));
+        extracted_data.add_reaction_count = Some(count("add_reaction"));
+        extracted_data.message_edited_count = Some(count("message_edited"));
+        extracted_data.sent_message_count = Some(count("send_message"));
+        extracted_data.slash_command_used_count = Some(count("application_command_used"));
+        extracted_data.average_open_count_per_day = Some(count("app_opened") / days);
+        extracted_data.average_message_count_per_day = Some(count("send_message") / days);
+    }
+
+    fn json_id(value: serde_json::Value) -> Option<String> {
+        match value {
+            serde_json::Value::String(s) => Some(s),
+            serde_json::Value::Number(n) => Some(n.to_string()),
+            _ => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn streams_events_and_skips_unreadable_lines() {
+        let log = [
+            r#"{"event_type": "join_call", "timestamp": "\"2023-01-01T10:00:00Z\"", "channel_id": 42}"#,
+            "",
+            r#"{"event_type": "app_opened"}"#,
+            "not json",
+            r#"{"event_type": "app_opened", "timestamp": "2023-01-02T10:00:00Z", "guild_id": "7"}"#,
+        ]
+        .join("\n");
+        let mut events = Vec::new();
+        let skipped = Parser::stream_events(log.as_bytes(), |event| {
+            events.push(event);
+            Ok(())
+        })
+        .unwrap();
+
+        assert_eq!(skipped, 2);
+        assert_eq!(events.len(), 2);
+        assert_eq!(events[0].timestamp, "2023-01-01T10:00:00Z");
+        assert_eq!(events[0].channel_id.as_deref(), Some("42"));
+        assert_eq!(events[1].guild_id.as_deref(), Some("7"));
+    }
+
+    #[test]
+    fn finds_the_log_files_in_order() {
+        let names = [
+            "activity/analytics/events-2023-00001-of-00002.json",
+            "activity/reporting/events-2023-00000-of-00001.json",
+            "activity/analytics/events-2023-00000-of-00002.json",
+            "activity/analytics/events-2023-00000-of-00002.json.bak",
+        ];
+        assert_eq!(
+            Parser::analytics_files(names.into_iter()).unwrap(),
+            [
+                "activity/analytics/events-2023-00000-of-00002.json",
+                "activity/analytics/events-2023-00001-of-00002.json",
+            ]
+        );
+    }
+
+    #[test]
+    fn averages_over_every_day_of_the_period() {
+        let mut data = ExtractedData::default();
+        let day = |date: &str, counts: &[(&str, usize)]| {
+            let counts = counts.iter().map(|(t, c)| (t.to_string(), *c)).collect();
+            (date.parse().unwrap(), counts)
+        };
+        data.analytics_daily_counts = BTreeMap::from([
+            day("2023-01-01", &[("app_opened", 6), ("send_message", 10)]),
+            day("2023-01-04", &[("app_opened", 2), ("join_call", 1)]),
+        ]);
+        Parser::apply_event_counts(&mut data);
+
+        assert_eq!(data.open_count, Some(8));
+        assert_eq!(data.sent_message_count, Some(10));
+        assert_eq!(data.join_call_count, Some(1));
+        assert_eq!(data.add_reaction_count, Some(0));
+        assert_eq!(data.average_open_count_per_day, Some(2));
+        assert_eq!(data.average_message_count_per_day, Some(2));
     }
 }
@@ -1,14 +1,17 @@
 use anyhow::Result;
 use chrono::Timelike;
 use regex::Regex;
-use std::collections::HashMap;
+use std::cmp::Reverse;
+use std::collections::{BTreeMap, HashMap};
 use std::io::{Read, Seek};
 use zip::ZipArchive;
 
 use crate::models::{
-    Channel, ExtractedData, FavoriteWord, Message, ParsedMessage, TopChannel, TopDM,
+    Channel, ChannelType, ChannelTypeStats, ExtractedData, FavoriteWord, Message, ParsedChannel,
+    ParsedMessage, TopChannel, TopDM, TopGroupDM, UserData,
 };
 use crate::parser::Parser;
+use crate::sentiment::{self, Lexicon};
 
 impl Parser {
     pub(super) fn load_channels<R: Read + Seek, F>(
@@ -28,15 +31,9 @@ impl Parser {
 
         let (is_old_package, is_old_package_v2) = self.detect_package_format(messages_root, &channel_ids);
 
-        let mut word_counts: HashMap<String, usize> = HashMap::new();
-        let mut channel_message_counts: Vec<(String, usize, String)> = Vec::new();
-        let mut dm_message_counts: Vec<(String, String, usize)> = Vec::new();
+        let mut channels: Vec<ParsedChannel> = Vec::new();
 
         for (index, channel_id) in channel_ids.iter().enumerate() {
-            if channel_ids.len() == 0 {
-                break;
-            }
-
             if index % 20 == 0 {
                 progress_callback(format!(
                     "Processing channel {} of {} (ID: {})",
@@ -65,7 +62,7 @@ impl Parser {
                 let channel: Channel = match self.parse_json(&data_content) {
                     Ok(ch) => ch,
                     Err(e) => {
-                        println!(
+                        eprintln!(
                             "[debug] Failed to parse channel data for {}: {}",
                             channel_id, e
                         );
@@ -73,70 +70,170 @@ impl Parser {
                     }
                 };
 
-                let messages: Vec<ParsedMessage> = if extension == "csv" {
+                let all_messages: Vec<ParsedMessage> = if extension == "csv" {
                     self.parse_csv(&messages_content)?
                 } else {
                     match self.parse_json_messages(&messages_content) {
                         Ok(m) => m,
                         Err(e) => {
-                            println!("[debug] Failed to parse messages for {}: {}", channel_id, e);
+                            eprintln!("[debug] Failed to parse messages for {}: {}", channel_id, e);
                             Vec::new()
                         }
                     }
                 };
 
+                let (messages, attachment_only) = all_messages
+                    .into_iter()
+                    .partition(|message| !message.contents.is_empty());
+
                 let name = messages_index
                     .get(&channel.id)
-                    .map(|s| s.as_str())
-                    .unwrap_or(&channel.id);
-                let is_dm = channel.recipients.as_ref().map_or(false, |r| r.len() == 2);
-                let dm_user_id = if is_dm {
-                    channel.recipients.as_ref().and_then(|recipients| {
-                        extracted_data
-                            .user
-                            .as_ref()
-                            .and_then(|user| recipients.iter().find(|&id| id != &user.id))
-                    })
-                } else {
-                    None
-                };
+                    .cloned()
+                    .unwrap_or_else(|| channel.id.clone());
 
-                let mut message_count = 0;
-                for message in &messages {
-                    message_count += 1;
-                    extracted_data.character_count += message.length;
-                    if let Ok(dt) = chrono::DateTime::parse_from_rfc3339(&message.timestamp) {
-                        extracted_data.hours_values[dt.hour() as usize] += 1;
-                    }
-                    for word in &message.words {
-                        if word.len() > 5 {
-                            *word_counts.entry(word.clone()).or_insert(0) += 1;
-                        }
+                channels.push(ParsedChannel {
+                    channel,
+                    name,
+                    messages,
+                    attachment_only,
+                });
+            }
+        }
+
+        extracted_data.channels = channels;
+        self.compute_channel_stats(extracted_data);
+
+        Ok(())
+    }
+
+    pub(super) fn compute_channel_stats(&self, extracted_data: &mut ExtractedData) {
+        extracted_data.utc_offset_minutes = Parser::utc_offset_minutes(&extracted_data.channels);
+        self.compute_message_stats(extracted_data);
+        self.compute_activity(extracted_data);
+        self.compute_languages(extracted_data);
+        self.compute_word_trends(extracted_data);
+        extracted_data.sentiment = sentiment::sentiment(extracted_data, Lexicon::bundled());
+        self.compute_content_stats(extracted_data);
+        self.compute_length_stats(extracted_data);
+        self.compute_years(extracted_data);
+    }
+
+    /// The offset most messages were recorded with, for events that carry none.
+    pub(super) fn utc_offset_minutes(channels: &[ParsedChannel]) -> i32 {
+        let mut offsets: HashMap<i32, usize> = HashMap::new();
+        for message in channels.iter().flat_map(|c| &c.messages) {
+            if let Some(local) = message.local_time() {
+                *offsets.entry(local.offset().local_minus_utc() / 60).or_insert(0) += 1;
+            }
+        }
+        offsets
+            .into_iter()
+            .max_by_key(|&(offset, count)| (count, Reverse(offset.abs()), offset))
+            .map_or(0, |(offset, _)| offset)
+    }
+
+    /// Counts, rankings, words and the hour and day histograms.
+    pub(super) fn compute_message_stats(&self, extracted_data: &mut ExtractedData) {
+        let mut word_counts: HashMap<String, usize> = HashMap::new();
+        let mut type_stats: BTreeMap<ChannelType, ChannelTypeStats> = BTreeMap::new();
+        let mut top_channels: Vec<TopChannel> = Vec::new();
+        let mut top_dms: Vec<TopDM> = Vec::new();
+        let mut top_group_dms: Vec<TopGroupDM> = Vec::new();
+
+        extracted_data.character_count = 0;
+        extracted_data.hours_values = vec![0; 24];
+        extracted_data.daily_values.clear();
+
+        // Relationships are the only place the package names other users.
+        let own_id = extracted_data.user.as_ref().map(|u| u.id.as_str());
+        let known_users: HashMap<&str, &UserData> = extracted_data
+            .user
+            .iter()
+            .flat_map(|u| u.relationships.iter())
+            .filter_map(|r| Some((r.id.as_str(), r.user.as_ref()?)))
+            .collect();
+        let other_recipients = |channel: &Channel| -> Vec<String> {
+            channel
+                .recipients
+                .iter()
+                .flatten()
+                .filter(|id| Some(id.as_str()) != own_id)
+                .cloned()
+                .collect()
+        };
+
+        for parsed in &extracted_data.channels {
+            let channel = &parsed.channel;
+            let kind = channel.kind();
+            let message_count = parsed.messages.len();
+
+            for message in &parsed.messages {
+                extracted_data.character_count += message.length;
+                if let Some(local) = message.local_time() {
+                    extracted_data.hours_values[local.hour() as usize] += 1;
+                }
+                if let Some(date) = message.local_date() {
+                    *extracted_data.daily_values.entry(date).or_insert(0) += 1;
+                }
+                for word in &message.words {
+                    if word.chars().count() >= self.options.min_word_length {
+                        *word_counts.entry(word.clone()).or_insert(0) += 1;
                     }
                 }
+            }
+
+            let stats = type_stats.entry(kind).or_insert(ChannelTypeStats {
+                channel_type: kind,
+                channel_count: 0,
+                message_count: 0,
+            });
+            stats.channel_count += 1;
+            stats.message_count += message_count;
 
-                if is_dm {
-                    if let Some(dm_id) = dm_user_id {
-                        dm_message_counts.push((channel.id.clone(), dm_id.clone(), message_count));
+            match kind {
+                ChannelType::Dm => {
+                    if let Some(dm_user_id) = other_recipients(channel).into_iter().next() {
+                        top_dms.push(TopDM {
+                            id: channel.id.clone(),
+                            user_data: known_users.get(dm_user_id.as_str()).map(|&u| u.clone()),
+                            dm_user_id,
+                            message_count,
+                            started_at: channel.created_at(),
+                        });
                     }
-                } else if let Some(guild) = &channel.guild {
-                    channel_message_counts.push((
-                        name.to_string(),
-                        message_count,
-                        guild.name.clone(),
-                    ));
                 }
+                ChannelType::GroupDm => top_group_dms.push(TopGroupDM {
+                    id: channel.id.clone(),
+                    name: parsed.name.clone(),
+                    message_count,
+                    created_at: channel.created_at(),
+                    participants: other_recipients(channel)
+                        .into_iter()
+                        .map(|id| match known_users.get(id.as_str()) {
+                            Some(&user) => user.clone(),
+                            None => UserData::from_id(id),
+                        })
+                        .collect(),
+                }),
+                _ => top_channels.push(TopChannel {
+                    name: parsed.name.clone(),
+                    message_count,
+                    guild_name: channel.guild.as_ref().map(|g| g.name.clone()),
+                    channel_type: kind,
+                    created_at: channel.created_at(),
+                    guild_created_at: channel.guild.as_ref().and_then(|g| g.created_at()),
+                }),
             }
         }
 
+        extracted_data.channel_types = type_stats.into_values().collect();
         self.finalize_channel_stats(
             extracted_data,
             word_counts,
-            channel_message_counts,
-            dm_message_counts,
+            top_channels,
+            top_dms,
+            top_group_dms,
         );
-
-        Ok(())
     }
 
     // Local helpers (private)
@@ -145,12 +242,15 @@ impl Parser {
         let mut messages = Vec::new();
         for result in reader.deserialize() {
             let record: Message = result?;
-            if !record.contents.is_empty() {
+            let attachments = Parser::process_attachments(record.attachments.as_deref());
+            if !record.contents.is_empty() || !attachments.is_empty() {
                 let words = Parser::process_words(&record.contents);
                 messages.push(ParsedMessage {
                     id: record.id,
                     timestamp: record.timestamp,
-                    length: record.contents.len(),
+                    length: record.contents.chars().count(),
+                    attachments,
+                    contents: record.contents,
                     words,
                 });
             }
@@ -168,15 +268,20 @@ impl Parser {
         };
         Ok(messages
             .into_iter()
-            .filter(|m| !m.contents.is_empty())
-            .map(|m| {
+            .filter_map(|m| {
+                let attachments = Parser::process_attachments(m.attachments.as_deref());
+                if m.contents.is_empty() && attachments.is_empty() {
+                    return None;
+                }
                 let words = Parser::process_words(&m.contents);
-                ParsedMessage {
+                Some(ParsedMessage {
                     id: m.id,
                     timestamp: m.timestamp,
-                    length: m.contents.len(),
+                    length: m.contents.chars().count(),
+                    attachments,
+                    contents: m.contents,
                     words,
-                }
+                })
             })
             .collect())
     }
@@ -190,7 +295,7 @@ impl Parser {
         let content = self.read_file(archive, &path)?;
         Ok(content
             .map(|c| self.parse_json(&c).unwrap_or_else(|e| {
-                println!("[debug] Failed to parse messages index: {}", e);
+                eprintln!("[debug] Failed to parse messages index: {}", e);
                 HashMap::new()
             }))
             .unwrap_or_default())
@@ -206,10 +311,10 @@ impl Parser {
         for i in 0..archive.len() {
             let file = archive.by_index(i)?;
             let name = file.name();
-            if name.starts_with(messages_root) {
-                if let Some(captures) = channel_regex.captures(name) {
-                    channel_ids.push(captures[1].to_string());
-                }
+            if name.starts_with(messages_root)
+                && let Some(captures) = channel_regex.captures(name)
+            {
+                channel_ids.push(captures[1].to_string());
             }
         }
         Ok(channel_ids)
@@ -222,8 +327,8 @@ impl Parser {
         let is_old_package_v2 = !channel_ids.iter().any(|id| {
             self.file_exists(&format!("{}/c{}/messages.json", messages_root, id))
         });
-        println!("[debug] Old package (2021): {}", is_old_package);
-        println!("[debug] Old package (2024): {}", is_old_package_v2);
+        eprintln!("[debug] Old package (2021): {}", is_old_package);
+        eprintln!("[debug] Old package (2024): {}", is_old_package_v2);
         (is_old_package, is_old_package_v2)
     }
 
@@ -231,45 +336,48 @@ impl Parser {
         &self,
         extracted_data: &mut ExtractedData,
         word_counts: HashMap<String, usize>,
-        mut channel_message_counts: Vec<(String, usize, String)>,
-        mut dm_message_counts: Vec<(String, String, usize)>,
+        mut top_channels: Vec<TopChannel>,
+        mut top_dms: Vec<TopDM>,
+        mut top_group_dms: Vec<TopGroupDM>,
     ) {
-        extracted_data.channel_count = channel_message_counts.len();
-        extracted_data.dm_channel_count = dm_message_counts.len();
-        extracted_data.message_count = channel_message_counts
+        let count_of = |channel_type: ChannelType| {
+            extracted_data
+                .channel_types
+                .iter()
+                .find(|s| s.channel_type == channel_type)
+                .map_or(0, |s| s.channel_count)
+        };
+        extracted_data.dm_channel_count = count_of(ChannelType::Dm);
+        extracted_data.group_dm_count = count_of(ChannelType::GroupDm);
+        extracted_data.channel_count = extracted_data
+            .channel_types
+            .iter()
+            .filter(|s| !s.channel_type.is_dm())
+            .map(|s| s.channel_count)
+            .sum();
+        extracted_data.message_count = extracted_data
+            .channel_types
             .iter()
-            .map(|(_, c, _)| *c)
-            .sum::<usize>()
-            + dm_message_counts.iter().map(|(_, _, c)| *c).sum::<usize>();
+            .map(|s| s.message_count)
+            .sum();
 
-        channel_message_counts.sort_by(|a, b| b.1.cmp(&a.1));
-        extracted_data.top_channels = channel_message_counts
-            .into_iter()
-            .take(10)
-            .map(|(name, count, guild)| TopChannel {
-                name,
-                message_count: count,
-                guild_name: Some(guild),
-            })
-            .collect();
+        top_channels.sort_by_key(|c| Reverse(c.message_count));
+        top_channels.truncate(self.options.top_count);
+        extracted_data.top_channels = top_channels;
 
-        dm_message_counts.sort_by(|a, b| b.2.cmp(&a.2));
-        extracted_data.top_dms = dm_message_counts
-            .into_iter()
-            .take(10)
-            .map(|(id, user_id, count)| TopDM {
-                id,
-                dm_user_id: user_id,
-                message_count: count,
-                user_data: None,
-            })
-            .collect();
+        top_dms.sort_by_key(|d| Reverse(d.message_count));
+        top_dms.truncate(self.options.top_count);
+        extracted_data.top_dms = top_dms;
+
+        top_group_dms.sort_by_key(|d| Reverse(d.message_count));
+        top_group_dms.truncate(self.options.top_count);
+        extracted_data.top_group_dms = top_group_dms;
 
         let mut word_vec: Vec<_> = word_counts.into_iter().collect();
-        word_vec.sort_by(|a, b| b.1.cmp(&a.1));
+        word_vec.sort_by_key(|w| Reverse(w.1));
         extracted_data.favorite_words = word_vec
             .into_iter()
-            .take(10)
+            .take(self.options.top_count)
             .map(|(word, count)| FavoriteWord { word, count })
             .collect();
     }
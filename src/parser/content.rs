@@ -0,0 +1,315 @@
+use std::collections::{HashMap, HashSet};
+
+use crate::models::{CodeLanguage, CommandUse, ContentShare, ContentStats, ExtractedData};
+use crate::parser::Parser;
+
+/// Characters bots commonly use as a command prefix besides `/`.
+const COMMAND_PREFIXES: &[char] = &['/', '!', '$', '%', ';'];
+
+/// Messages with fewer letters than this are too short to count as shouting ("OK", "GG").
+const MIN_SHOUTING_LETTERS: usize = 5;
+
+impl Parser {
+    /// Counts questions, links, code blocks, bot commands, messages to bots and shouting.
+    pub(super) fn compute_content_stats(&self, extracted_data: &mut ExtractedData) {
+        let mut questions = 0;
+        let mut links = 0;
+        let mut code = 0;
+        let mut command_count = 0;
+        let mut to_bots = 0;
+        let mut shouting = 0;
+        let mut code_languages: HashMap<String, usize> = HashMap::new();
+        let mut commands: HashMap<String, usize> = HashMap::new();
+        let mut message_count = 0;
+        let bots = bot_ids(extracted_data);
+
+        for parsed in &extracted_data.channels {
+            // Everything said in a DM with a bot is said to it.
+            let bot_dm = parsed
+                .channel
+                .recipients
+                .iter()
+                .flatten()
+                .any(|id| bots.contains(id.as_str()));
+            for message in &parsed.messages {
+                let contents = message.contents.as_str();
+                message_count += 1;
+
+                let blocks = code_blocks(contents);
+                let prose = strip_code_blocks(contents);
+                let command = command(contents);
+
+                if prose
+                    .split_whitespace()
+                    .any(|word| !is_link(word) && word.contains(['?', '¿']))
+                {
+                    questions += 1;
+                }
+                if contents.split_whitespace().any(is_link) {
+                    links += 1;
+                }
+                if !blocks.is_empty() {
+                    code += 1;
+                }
+                for language in blocks {
+                    *code_languages.entry(language).or_insert(0) += 1;
+                }
+                if let Some(command) = command {
+                    command_count += 1;
+                    *commands.entry(command.to_lowercase()).or_insert(0) += 1;
+                }
+                if bot_dm || mentions(contents).any(|id| bots.contains(id)) {
+                    to_bots += 1;
+                }
+                if is_shouting(&prose) {
+                    shouting += 1;
+                }
+            }
+        }
+
+        let share = |count: usize| ContentShare {
+            message_count: count,
+            share: if message_count == 0 {
+                0.0
+            } else {
+                count as f64 / message_count as f64
+            },
+        };
+
+        let mut code_languages: Vec<CodeLanguage> = code_languages
+            .into_iter()
+            .map(|(language, block_count)| CodeLanguage {
+                language,
+                block_count,
+            })
+            .collect();
+        code_languages.sort_by(|a, b| {
+            b.block_count
+                .cmp(&a.block_count)
+                .then_with(|| a.language.cmp(&b.language))
+        });
+
+        let mut top_commands: Vec<CommandUse> = commands
+            .into_iter()
+            .map(|(command, count)| CommandUse { command, count })
+            .collect();
+        top_commands.sort_by(|a, b| {
+            b.count
+                .cmp(&a.count)
+                .then_with(|| a.command.cmp(&b.command))
+        });
+        top_commands.truncate(self.options.top_count);
+
+        extracted_data.content = ContentStats {
+            questions: share(questions),
+            links: share(links),
+            code: share(code),
+            commands: share(command_count),
+            replies_to_bots: share(to_bots),
+            shouting: share(shouting),
+            code_languages,
+            top_commands,
+        };
+    }
+}
+
+/// Bot user ids the package knows of. Bot applications share their id with their bot user.
+fn bot_ids(extracted_data: &ExtractedData) -> HashSet<&str> {
+    let Some(user) = &extracted_data.user else {
+        return HashSet::new();
+    };
+    let friends = user
+        .relationships
+        .iter()
+        .filter(|r| r.user.as_ref().is_some_and(|u| u.bot))
+        .map(|r| r.id.as_str());
+    let applications = user
+        .entitlements
+        .iter()
+        .filter_map(|e| e.application_id.as_deref());
+    friends.chain(applications).collect()
+}
+
+/// The user ids mentioned as `<@id>` or `<@!id>`.
+fn mentions(contents: &str) -> impl Iterator<Item = &str> {
+    contents.split("<@").skip(1).filter_map(|rest| {
+        let (id, _) = rest.trim_start_matches('!').split_once('>')?;
+        (!id.is_empty() && id.bytes().all(|b| b.is_ascii_digit())).then_some(id)
+    })
+}
+
+fn is_link(word: &str) -> bool {
+    let word = word.trim_start_matches('<');
+    word.starts_with("https://") || word.starts_with("http://") || word.starts_with("www.")
+}
+
+/// The declared language of every closed ```` ``` ```` block, lowercased.
+fn code_blocks(contents: &str) -> Vec<String> {
+    let parts: Vec<&str> = contents.split("```").collect();
+    // Text between an opening and a closing fence sits at the odd indices; a trailing odd
+    // part without a closing fence isn't a block.
+    (1..parts.len().saturating_sub(1))
+        .step_by(2)
+        .map(|index| {
+            let body = parts[index];
+            match body.split_once('\n') {
+                Some((first, _)) if !first.trim().is_empty() && !first.trim().contains(' ') => {
+                    first.trim().to_lowercase()
+                }
+                _ => "plain".to_string(),
+            }
+        })
+        .collect()
+}
+
+fn strip_code_blocks(contents: &str) -> String {
+    let parts: Vec<&str> = contents.split("```").collect();
+    let closed = parts.len().saturating_sub(1) / 2 * 2;
+    parts
+        .iter()
+        .enumerate()
+        .filter(|(index, _)| index % 2 == 0 || *index > closed)
+        .map(|(_, part)| *part)
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// The command a message starts with, such as `/roll` or `!play`.
+fn command(contents: &str) -> Option<&str> {
+    let first = contents.split_whitespace().next()?;
+    let mut chars = first.chars();
+    let prefix = chars.next()?;
+    let name = chars.as_str();
+    let valid = COMMAND_PREFIXES.contains(&prefix)
+        && name.chars().next().is_some_and(|c| c.is_ascii_alphabetic())
+        && name.len() <= 32
+        && name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-');
+    valid.then_some(first)
+}
+
+fn is_shouting(text: &str) -> bool {
+    let mut letters = 0;
+    for word in text.split_whitespace().filter(|word| !is_link(word)) {
+        for c in word.chars().filter(|c| c.is_alphabetic()) {
+            if c.is_lowercase() {
+                return false;
+            }
+            if c.is_uppercase() {
+                letters += 1;
+            }
+        }
+    }
+    letters >= MIN_SHOUTING_LETTERS
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::fixtures::{data, dm_channel, guild_channel, user};
+
+    #[test]
+    fn reads_fenced_code_languages() {
+        assert_eq!(code_blocks("```rust\nfn main() {}\n```"), ["rust"]);
+        assert_eq!(
+            code_blocks("a ```\nplain\n``` b ```Python\nx = 1\n```"),
+            ["plain", "python"]
+        );
+        // One-line blocks have no language line, and an unclosed fence isn't a block.
+        assert_eq!(code_blocks("```let x = 1;```"), ["plain"]);
+        assert!(code_blocks("```rust\nunclosed").is_empty());
+    }
+
+    #[test]
+    fn strips_closed_code_blocks_only() {
+        assert_eq!(strip_code_blocks("why ```rust\nx?\n``` now"), "why   now");
+        assert_eq!(strip_code_blocks("a ```b"), "a  b");
+    }
+
+    #[test]
+    fn recognizes_prefix_commands() {
+        assert_eq!(command("/roll 1d20"), Some("/roll"));
+        assert_eq!(command("!play never gonna"), Some("!play"));
+        assert_eq!(command("$ ls"), None);
+        assert_eq!(command("!!!"), None);
+        assert_eq!(command(":) /roll"), None);
+        assert_eq!(command("/usr/bin/env"), None);
+    }
+
+    #[test]
+    fn shouting_needs_enough_capitals_and_no_lowercase() {
+        assert!(is_shouting("WHAT IS THIS"));
+        assert!(is_shouting("NO WAY https://example.com/page"));
+        assert!(!is_shouting("OK GG"));
+        assert!(!is_shouting("WHAT is this"));
+    }
+
+    #[test]
+    fn shouting_starts_at_the_minimum_letter_count() {
+        let letters = |n: usize| "A".repeat(n);
+        assert!(!is_shouting(&letters(MIN_SHOUTING_LETTERS - 1)));
+        assert!(is_shouting(&letters(MIN_SHOUTING_LETTERS)));
+        // Digits and punctuation don't count towards it.
+        let almost = letters(MIN_SHOUTING_LETTERS - 1);
+        assert!(!is_shouting(&format!("{}!!! 123", almost)));
+        assert!(!is_shouting(""));
+    }
+
+    #[test]
+    fn empty_package_has_no_shares() {
+        let mut data = data(vec![guild_channel("1", "general", &[])]);
+        Parser::new().compute_content_stats(&mut data);
+
+        let content = &data.content;
+        assert_eq!(content.questions.message_count, 0);
+        assert_eq!(content.questions.share, 0.0);
+        assert!(content.code_languages.is_empty());
+        assert!(content.top_commands.is_empty());
+    }
+
+    #[test]
+    fn reads_mentions() {
+        let ids: Vec<&str> = mentions("hey <@42> and <@!43>, not <@&44> or <#45>").collect();
+        assert_eq!(ids, ["42", "43"]);
+    }
+
+    #[test]
+    fn counts_messages_by_kind() {
+        let bot = "555555555555555555";
+        let app = "666666666666666666";
+        let mut data = data(vec![
+            guild_channel(
+                "1",
+                "general",
+                &[
+                    ("2023-01-01T10:00:00Z", "is this right?"),
+                    ("2023-01-01T10:01:00Z", "see https://example.com/?q=1"),
+                    ("2023-01-01T10:02:00Z", "!play something"),
+                    ("2023-01-01T10:03:00Z", "THIS IS GREAT"),
+                    ("2023-01-01T10:04:00Z", "```rust\nlet x = y?;\n```"),
+                    ("2023-01-01T10:05:00Z", &format!("<@{}> play it again", app)),
+                ],
+            ),
+            dm_channel("2", bot, &[("2023-01-01T11:00:00Z", "hello bot")]),
+        ]);
+        data.user = Some(user(serde_json::json!({
+            "relationships": [
+                {"id": bot, "type": 1, "user": {"id": bot, "username": "bot", "avatar": null, "bot": true}},
+            ],
+            "entitlements": [{"id": "1", "sku_id": "2", "application_id": app}],
+        })));
+        Parser::new().compute_content_stats(&mut data);
+
+        let content = &data.content;
+        assert_eq!(content.questions.message_count, 1);
+        assert_eq!(content.links.message_count, 1);
+        assert_eq!(content.commands.message_count, 1);
+        assert_eq!(content.shouting.message_count, 1);
+        assert_eq!(content.code.message_count, 1);
+        assert_eq!(content.code_languages[0].language, "rust");
+        assert_eq!(content.top_commands[0].command, "!play");
+        assert_eq!(content.replies_to_bots.message_count, 2);
+        assert_eq!(content.questions.share, 1.0 / 7.0);
+    }
+}
@@ -0,0 +1,250 @@
+use anyhow::Result;
+use std::cmp::Reverse;
+use std::collections::{HashMap, HashSet};
+use std::io::{Read, Seek};
+use zip::ZipArchive;
+
+use crate::models::{
+    ChannelGrowth, ChannelType, DMChange, ExtractedData, FieldChange, GuildChange, PackageDiff, User,
+};
+use crate::parser::Parser;
+
+impl Parser {
+    /// Parses an older and a newer package and reports what changed between them.
+    pub fn diff_packages<R: Read + Seek, F>(
+        &mut self,
+        old_archive: ZipArchive<R>,
+        new_archive: ZipArchive<R>,
+        progress_callback: F,
+    ) -> Result<PackageDiff>
+    where
+        F: Fn(String) + Send + Sync,
+    {
+        progress_callback("Parsing older package...".to_string());
+        let old = self.parse_archive(old_archive, &progress_callback)?;
+        progress_callback("Parsing newer package...".to_string());
+        let new = self.parse_archive(new_archive, &progress_callback)?;
+
+        progress_callback("Comparing packages...".to_string());
+        Ok(Parser::diff_extracted(&old, &new))
+    }
+
+    /// Compares two already parsed packages.
+    pub fn diff_extracted(old: &ExtractedData, new: &ExtractedData) -> PackageDiff {
+        let mut new_guilds: Vec<GuildChange> = new
+            .guilds
+            .iter()
+            .filter(|(id, _)| !old.guilds.contains_key(*id))
+            .map(|(id, name)| GuildChange {
+                id: id.clone(),
+                name: name.clone(),
+            })
+            .collect();
+        new_guilds.sort_by(|a, b| a.name.cmp(&b.name));
+
+        let mut removed_guilds: Vec<GuildChange> = old
+            .guilds
+            .iter()
+            .filter(|(id, _)| !new.guilds.contains_key(*id))
+            .map(|(id, name)| GuildChange {
+                id: id.clone(),
+                name: name.clone(),
+            })
+            .collect();
+        removed_guilds.sort_by(|a, b| a.name.cmp(&b.name));
+
+        let old_counts: HashMap<&str, usize> = old
+            .channels
+            .iter()
+            .map(|c| (c.channel.id.as_str(), c.messages.len()))
+            .collect();
+
+        let user_id = new.user.as_ref().map(|u| u.id.as_str());
+        let mut new_dms = Vec::new();
+        let mut channel_growth = Vec::new();
+
+        for parsed in &new.channels {
+            let new_count = parsed.messages.len();
+            let old_count = old_counts.get(parsed.channel.id.as_str()).copied();
+
+            if old_count.is_none() {
+                let dm_user_id = parsed
+                    .channel
+                    .recipients
+                    .as_ref()
+                    .filter(|_| parsed.channel.kind() == ChannelType::Dm)
+                    .and_then(|recipients| recipients.iter().find(|id| Some(id.as_str()) != user_id));
+                if let Some(dm_user_id) = dm_user_id {
+                    new_dms.push(DMChange {
+                        channel_id: parsed.channel.id.clone(),
+                        dm_user_id: dm_user_id.clone(),
+                        message_count: new_count,
+                    });
+                }
+            }
+
+            let old_count = old_count.unwrap_or(0);
+            if new_count > old_count {
+                channel_growth.push(ChannelGrowth {
+                    channel_id: parsed.channel.id.clone(),
+                    name: parsed.name.clone(),
+                    old_count,
+                    new_count,
+                });
+            }
+        }
+        new_dms.sort_by_key(|dm| Reverse(dm.message_count));
+        channel_growth.sort_by_key(|growth| Reverse(growth.new_count - growth.old_count));
+
+        let old_payments: HashSet<String> = old
+            .user
+            .iter()
+            .flat_map(|u| u.payments.iter().map(Parser::payment_key))
+            .collect();
+        let new_payments = new
+            .user
+            .iter()
+            .flat_map(|u| u.payments.iter())
+            .filter(|p| !old_payments.contains(&Parser::payment_key(p)))
+            .cloned()
+            .collect();
+
+        PackageDiff {
+            new_guilds,
+            removed_guilds,
+            new_dms,
+            channel_growth,
+            new_payments,
+            account_changes: Parser::diff_account(old.user.as_ref(), new.user.as_ref()),
+        }
+    }
+
+    fn diff_account(old: Option<&User>, new: Option<&User>) -> Vec<FieldChange> {
+        // Lists are summarised instead of dumped, see the counts below.
+        const IGNORED_FIELDS: [&str; 9] = [
+            "avatar_extension",
+            "default_avatar_index",
+            "payments",
+            "connections",
+            "relationships",
+            "guild_settings",
+            "entitlements",
+            "gift_codes",
+            "notes",
+        ];
+
+        let to_fields = |user: Option<&User>| -> HashMap<String, serde_json::Value> {
+            match user.map(serde_json::to_value) {
+                Some(Ok(serde_json::Value::Object(map))) => map.into_iter().collect(),
+                _ => HashMap::new(),
+            }
+        };
+        let old_fields = to_fields(old);
+        let new_fields = to_fields(new);
+
+        let mut keys: Vec<&String> = old_fields.keys().chain(new_fields.keys()).collect();
+        keys.sort();
+        keys.dedup();
+
+        let mut changes: Vec<FieldChange> = keys
+            .into_iter()
+            .filter(|key| !IGNORED_FIELDS.contains(&key.as_str()))
+            .filter(|key| old_fields.get(*key) != new_fields.get(*key))
+            .map(|key| FieldChange {
+                field: key.clone(),
+                old_value: old_fields.get(key).map(field_value),
+                new_value: new_fields.get(key).map(field_value),
+            })
+            .collect();
+
+        let summary = |user: Option<&User>| -> Vec<(&'static str, String)> {
+            user.map(|u| {
+                vec![
+                    ("friend_count", u.friend_count().to_string()),
+                    ("blocked_count", u.blocked_count().to_string()),
+                    ("linked_services", u.linked_services().join(", ")),
+                ]
+            })
+            .unwrap_or_default()
+        };
+        let old_summary: HashMap<_, _> = summary(old).into_iter().collect();
+        for (field, new_value) in summary(new) {
+            let old_value = old_summary.get(field);
+            if old_value != Some(&new_value) {
+                changes.push(FieldChange {
+                    field: field.to_string(),
+                    old_value: old_value.cloned(),
+                    new_value: Some(new_value),
+                });
+            }
+        }
+        changes
+    }
+}
+
+/// Strings are shown without their JSON quotes, everything else as JSON.
+fn field_value(value: &serde_json::Value) -> String {
+    match value {
+        serde_json::Value::String(s) => s.clone(),
+        other => other.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::fixtures::{data, dm_channel, guild_channel, user};
+    use serde_json::json;
+
+    #[test]
+    fn reports_new_guilds_dms_growth_and_account_changes() {
+        let mut old = data(vec![guild_channel("1", "general", &[("2023-01-01T10:00:00Z", "a")])]);
+        old.guilds.insert("700000000000000001".to_string(), "Old Guild".to_string());
+        old.user = Some(user(json!({"username": "before", "relationships": []})));
+
+        let mut new = data(vec![
+            guild_channel(
+                "1",
+                "general",
+                &[("2023-01-01T10:00:00Z", "a"), ("2023-02-01T10:00:00Z", "b")],
+            ),
+            dm_channel("2", "222222222222222222", &[("2023-02-02T10:00:00Z", "hi")]),
+        ]);
+        new.guilds.insert("700000000000000002".to_string(), "New Guild".to_string());
+        new.user = Some(user(json!({
+            "username": "after",
+            "relationships": [{"id": "222222222222222222", "type": 1}],
+        })));
+
+        let diff = Parser::diff_extracted(&old, &new);
+        assert_eq!(diff.new_guilds[0].name, "New Guild");
+        assert_eq!(diff.removed_guilds[0].name, "Old Guild");
+        assert_eq!(diff.new_dms.len(), 1);
+        assert_eq!(diff.new_dms[0].dm_user_id, "222222222222222222");
+        let growth: Vec<(&str, usize, usize)> = diff
+            .channel_growth
+            .iter()
+            .map(|g| (g.channel_id.as_str(), g.old_count, g.new_count))
+            .collect();
+        assert_eq!(growth, [("1", 1, 2), ("2", 0, 1)]);
+
+        let fields: Vec<(&str, Option<&str>)> = diff
+            .account_changes
+            .iter()
+            .map(|c| (c.field.as_str(), c.new_value.as_deref()))
+            .collect();
+        assert_eq!(fields, [("username", Some("after")), ("friend_count", Some("1"))]);
+        assert!(diff.to_string().contains("username: before -> after"));
+    }
+
+    #[test]
+    fn identical_packages_have_no_changes() {
+        let channel = guild_channel("1", "general", &[("2023-01-01T10:00:00Z", "a")]);
+        let mut package = data(vec![channel]);
+        package.user = Some(user(json!({})));
+        let diff = Parser::diff_extracted(&package, &package);
+        assert!(diff.new_guilds.is_empty() && diff.removed_guilds.is_empty());
+        assert!(diff.channel_growth.is_empty() && diff.new_dms.is_empty());
+        assert!(diff.new_payments.is_empty() && diff.account_changes.is_empty());
+    }
+}
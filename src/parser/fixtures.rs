@@ -0,0 +1,119 @@
+//! Small hand-built packages for the unit tests.
+
+use std::collections::HashSet;
+use std::io::{Cursor, Write};
+use std::path::PathBuf;
+use zip::write::SimpleFileOptions;
+use zip::{CompressionMethod, ZipArchive, ZipWriter};
+
+use crate::models::{Channel, ExtractedData, Guild, ParsedChannel, ParsedMessage, User};
+use crate::parser::Parser;
+
+pub(crate) const USER_ID: &str = "123456789012345678";
+
+/// A message sent at `timestamp` (RFC 3339). Ids this small carry no timestamp, so only
+/// `timestamp` dates it.
+pub(crate) fn message(id: u64, timestamp: &str, contents: &str) -> ParsedMessage {
+    ParsedMessage {
+        id,
+        timestamp: timestamp.to_string(),
+        contents: contents.to_string(),
+        attachments: Vec::new(),
+        length: contents.chars().count(),
+        words: Parser::process_words(contents),
+    }
+}
+
+/// Messages with ids counting up from 1.
+fn numbered(messages: &[(&str, &str)]) -> Vec<ParsedMessage> {
+    (1..)
+        .zip(messages)
+        .map(|(id, (timestamp, contents))| message(id, timestamp, contents))
+        .collect()
+}
+
+/// A guild channel with one message per `(timestamp, contents)` pair.
+pub(crate) fn guild_channel(id: &str, name: &str, messages: &[(&str, &str)]) -> ParsedChannel {
+    ParsedChannel {
+        channel: Channel {
+            id: id.to_string(),
+            channel_type: None,
+            recipients: None,
+            guild: Some(Guild {
+                id: "700000000000000001".to_string(),
+                name: "Guild".to_string(),
+            }),
+        },
+        name: name.to_string(),
+        messages: numbered(messages),
+        attachment_only: Vec::new(),
+    }
+}
+
+/// A package with just these channels.
+pub(crate) fn data(channels: Vec<ParsedChannel>) -> ExtractedData {
+    ExtractedData {
+        channels,
+        ..ExtractedData::default()
+    }
+}
+
+/// A DM between the account and `recipient`.
+pub(crate) fn dm_channel(id: &str, recipient: &str, messages: &[(&str, &str)]) -> ParsedChannel {
+    ParsedChannel {
+        channel: Channel {
+            id: id.to_string(),
+            channel_type: None,
+            recipients: Some(vec![USER_ID.to_string(), recipient.to_string()]),
+            guild: None,
+        },
+        name: format!("DM {}", recipient),
+        messages: numbered(messages),
+        attachment_only: Vec::new(),
+    }
+}
+
+/// The account, with `extra` merged into its `user.json`.
+pub(crate) fn user(extra: serde_json::Value) -> User {
+    let mut json = serde_json::json!({
+        "id": USER_ID,
+        "username": "tester",
+        "discriminator": 0,
+    });
+    if let (Some(json), Some(extra)) = (json.as_object_mut(), extra.as_object()) {
+        json.extend(extra.clone());
+    }
+    serde_json::from_value(json).unwrap()
+}
+
+/// Fills in the channel statistics of `data`, for tests outside the parser.
+pub(crate) fn compute_stats(data: &mut ExtractedData) {
+    Parser::new().compute_channel_stats(data);
+}
+
+/// An in-memory zip holding `files`, plus a server so the package layout is recognized.
+/// Folders get their own entries, as in the packages Discord sends.
+pub(crate) fn package(files: &[(&str, String)]) -> ZipArchive<Cursor<Vec<u8>>> {
+    let mut zip = ZipWriter::new(Cursor::new(Vec::new()));
+    let options = SimpleFileOptions::default().compression_method(CompressionMethod::Stored);
+    let server = (
+        "Servers/700000000000000001/guild.json",
+        r#"{"id": "700000000000000001", "name": "Guild"}"#.to_string(),
+    );
+    let mut folders = HashSet::new();
+    for (name, contents) in files.iter().chain([&server]) {
+        if let Some((folder, _)) = name.rsplit_once('/')
+            && folders.insert(folder.to_string())
+        {
+            zip.add_directory(folder, options).unwrap();
+        }
+        zip.start_file(*name, options).unwrap();
+        zip.write_all(contents.as_bytes()).unwrap();
+    }
+    ZipArchive::new(zip.finish().unwrap()).unwrap()
+}
+
+/// A path in the temp directory that no other test process uses.
+pub(crate) fn temp_path(name: &str) -> PathBuf {
+    std::env::temp_dir().join(format!("dpkv-test-{}-{}", std::process::id(), name))
+}
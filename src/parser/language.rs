@@ -0,0 +1,290 @@
+use std::cmp::Reverse;
+use std::collections::{HashMap, HashSet};
+use whatlang::{Detector, Lang};
+
+use crate::models::{
+    ChannelLanguages, ExtractedData, FavoriteWord, LanguageCount, LanguageShare, LanguageStats,
+};
+use crate::parser::Parser;
+
+/// whatlang's own "reliable" cutoff (0.9) rejects most chat messages, which are short.
+const MIN_CONFIDENCE: f64 = 0.5;
+
+impl Parser {
+    /// Detects the language of every message and builds per-language favorite words.
+    ///
+    /// Messages are first checked against every language. Those that stay undecided get a
+    /// second try limited to the languages found in the first pass, which settles many short
+    /// messages without mistaking them for a language the account never writes in.
+    pub(super) fn compute_languages(&self, extracted_data: &mut ExtractedData) {
+        let detect = |detector: &Detector, contents: &str| {
+            detector
+                .detect(&Parser::tokens(contents).join(" "))
+                .filter(|info| info.confidence() >= MIN_CONFIDENCE)
+                .map(|info| info.lang())
+        };
+        let detector = Detector::new();
+        let mut detected: Vec<Vec<Option<Lang>>> = extracted_data
+            .channels
+            .iter()
+            .map(|parsed| {
+                parsed
+                    .messages
+                    .iter()
+                    .map(|m| detect(&detector, &m.contents))
+                    .collect()
+            })
+            .collect();
+        let seen: HashSet<Lang> = detected.iter().flatten().flatten().copied().collect();
+        if !seen.is_empty() {
+            let detector = Detector::with_allowlist(seen.into_iter().collect());
+            for (parsed, langs) in extracted_data.channels.iter().zip(&mut detected) {
+                for (message, lang) in parsed.messages.iter().zip(langs) {
+                    if lang.is_none() {
+                        *lang = detect(&detector, &message.contents);
+                    }
+                }
+            }
+        }
+
+        let mut totals: HashMap<Lang, usize> = HashMap::new();
+        let mut word_counts: HashMap<Lang, HashMap<String, usize>> = HashMap::new();
+        let mut stopwords: HashMap<Lang, Option<HashSet<&'static str>>> = HashMap::new();
+        let mut channels: Vec<ChannelLanguages> = Vec::new();
+        let mut undetected_count = 0;
+
+        for (parsed, langs) in extracted_data.channels.iter().zip(detected) {
+            let mut counts: HashMap<Lang, usize> = HashMap::new();
+            for (message, lang) in parsed.messages.iter().zip(langs) {
+                let Some(lang) = lang else {
+                    undetected_count += 1;
+                    continue;
+                };
+                let tokens = Parser::tokens(&message.contents);
+                *counts.entry(lang).or_insert(0) += 1;
+
+                let stopwords = stopwords.entry(lang).or_insert_with(|| stopword_set(lang));
+                let words = word_counts.entry(lang).or_default();
+                for token in tokens {
+                    let keep = match stopwords {
+                        Some(stopwords) => {
+                            token.chars().count() > 2
+                                && !stopwords.contains(token.as_str())
+                                && !token.chars().all(|c| c.is_numeric())
+                        }
+                        // Without a stopword list the length heuristic is all we have.
+                        None => token.chars().count() >= self.options.min_word_length,
+                    };
+                    if keep {
+                        *words.entry(token).or_insert(0) += 1;
+                    }
+                }
+            }
+
+            let detected: usize = counts.values().sum();
+            if detected == 0 {
+                continue;
+            }
+            let mut languages: Vec<LanguageCount> = counts
+                .iter()
+                .map(|(lang, count)| LanguageCount {
+                    code: lang.code().to_string(),
+                    message_count: *count,
+                    share: *count as f64 / detected as f64,
+                })
+                .collect();
+            languages.sort_by(|a, b| {
+                b.message_count
+                    .cmp(&a.message_count)
+                    .then_with(|| a.code.cmp(&b.code))
+            });
+            channels.push(ChannelLanguages {
+                channel_id: parsed.channel.id.clone(),
+                name: parsed.name.clone(),
+                message_count: parsed.messages.len(),
+                languages,
+            });
+            for (lang, count) in counts {
+                *totals.entry(lang).or_insert(0) += count;
+            }
+        }
+
+        let detected: usize = totals.values().sum();
+        let mut languages: Vec<LanguageShare> = totals
+            .into_iter()
+            .map(|(lang, message_count)| {
+                let mut words: Vec<_> = word_counts
+                    .remove(&lang)
+                    .unwrap_or_default()
+                    .into_iter()
+                    .collect();
+                words.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+                LanguageShare {
+                    code: lang.code().to_string(),
+                    name: lang.eng_name().to_string(),
+                    message_count,
+                    share: message_count as f64 / detected as f64,
+                    favorite_words: words
+                        .into_iter()
+                        .take(self.options.top_count)
+                        .map(|(word, count)| FavoriteWord { word, count })
+                        .collect(),
+                }
+            })
+            .collect();
+        languages.sort_by(|a, b| {
+            b.message_count
+                .cmp(&a.message_count)
+                .then_with(|| a.code.cmp(&b.code))
+        });
+
+        channels.sort_by_key(|c| Reverse(c.message_count));
+        channels.truncate(self.options.top_count);
+
+        extracted_data.languages = LanguageStats {
+            languages,
+            channels,
+            undetected_count,
+        };
+    }
+}
+
+fn stopword_set(lang: Lang) -> Option<HashSet<&'static str>> {
+    stopwords_code(lang).map(|code| stop_words::get(code).iter().copied().collect())
+}
+
+/// The stopwords of every detected language, or English ones when none was detected.
+pub(super) fn detected_stopwords(extracted_data: &ExtractedData) -> HashSet<&'static str> {
+    let mut langs: Vec<Lang> = extracted_data
+        .languages
+        .languages
+        .iter()
+        .filter_map(|language| Lang::from_code(&language.code))
+        .collect();
+    if langs.is_empty() {
+        langs.push(Lang::Eng);
+    }
+    langs
+        .into_iter()
+        .filter_map(stopword_set)
+        .flatten()
+        .collect()
+}
+
+/// The ISO 639-1 code of the bundled stopword list for `lang`, if there is one.
+fn stopwords_code(lang: Lang) -> Option<&'static str> {
+    Some(match lang {
+        Lang::Afr => "af",
+        Lang::Ara => "ar",
+        Lang::Ben => "bn",
+        Lang::Bul => "bg",
+        Lang::Cat => "ca",
+        Lang::Ces => "cs",
+        Lang::Cmn => "zh",
+        Lang::Dan => "da",
+        Lang::Deu => "de",
+        Lang::Ell => "el",
+        Lang::Eng => "en",
+        Lang::Epo => "eo",
+        Lang::Est => "et",
+        Lang::Fin => "fi",
+        Lang::Fra => "fr",
+        Lang::Guj => "gu",
+        Lang::Heb => "he",
+        Lang::Hin => "hi",
+        Lang::Hrv => "hr",
+        Lang::Hun => "hu",
+        Lang::Hye => "hy",
+        Lang::Ind => "id",
+        Lang::Ita => "it",
+        Lang::Jpn => "ja",
+        Lang::Kor => "ko",
+        Lang::Lat => "la",
+        Lang::Lav => "lv",
+        Lang::Lit => "lt",
+        Lang::Mar => "mr",
+        Lang::Nld => "nl",
+        Lang::Nob => "no",
+        Lang::Pes => "fa",
+        Lang::Pol => "pl",
+        Lang::Por => "pt",
+        Lang::Ron => "ro",
+        Lang::Rus => "ru",
+        Lang::Slk => "sk",
+        Lang::Slv => "sl",
+        Lang::Spa => "es",
+        Lang::Swe => "sv",
+        Lang::Tgl => "tl",
+        Lang::Tha => "th",
+        Lang::Tur => "tr",
+        Lang::Ukr => "uk",
+        Lang::Urd => "ur",
+        Lang::Vie => "vi",
+        Lang::Zul => "zu",
+        _ => return None,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::fixtures::{data, guild_channel};
+
+    #[test]
+    fn detects_languages_and_skips_stopwords() {
+        let mut data = data(vec![
+            guild_channel(
+                "1",
+                "english",
+                &[
+                    (
+                        "2023-01-01T10:00:00Z",
+                        "The weather is lovely today and I am going outside",
+                    ),
+                    (
+                        "2023-01-01T10:01:00Z",
+                        "I think the weather will be lovely tomorrow as well",
+                    ),
+                ],
+            ),
+            guild_channel(
+                "2",
+                "deutsch",
+                &[(
+                    "2023-01-01T11:00:00Z",
+                    "Ich gehe heute mit meinen Freunden in den schönen Wald spazieren",
+                )],
+            ),
+            guild_channel("3", "noise", &[("2023-01-01T12:00:00Z", "12345 ???")]),
+        ]);
+        Parser::new().compute_languages(&mut data);
+
+        let languages = &data.languages;
+        let codes: Vec<&str> = languages
+            .languages
+            .iter()
+            .map(|l| l.code.as_str())
+            .collect();
+        assert_eq!(codes, ["eng", "deu"]);
+        assert_eq!(languages.undetected_count, 1);
+        let english = &languages.languages[0];
+        assert_eq!(english.name, "English");
+        assert_eq!(english.share, 2.0 / 3.0);
+        assert_eq!(english.favorite_words[0].word, "lovely");
+        assert!(english.favorite_words.iter().all(|w| w.word != "the"));
+        assert_eq!(languages.channels[0].languages[0].code, "eng");
+    }
+
+    #[test]
+    fn every_stopword_list_exists() {
+        for lang in Lang::all() {
+            if let Some(code) = stopwords_code(*lang) {
+                assert!(
+                    !stop_words::get(code).is_empty(),
+                    "no stopwords for {}",
+                    code
+                );
+            }
+        }
+    }
+}
@@ -0,0 +1,163 @@
+use std::cmp::Reverse;
+
+use crate::models::{ChannelLength, ExtractedData, LengthBucket, LengthStats};
+use crate::parser::Parser;
+use crate::parser::utils::{median, percentile};
+
+/// Lower bounds of the histogram buckets. Discord caps messages at 2000 characters, 4000
+/// with Nitro.
+const BUCKET_STARTS: &[usize] = &[1, 2, 6, 11, 21, 51, 101, 201, 501, 1001, 2001];
+
+impl Parser {
+    /// Length distribution overall and the average length per channel and DM.
+    pub(super) fn compute_length_stats(&self, extracted_data: &mut ExtractedData) {
+        let mut histogram: Vec<LengthBucket> = BUCKET_STARTS
+            .iter()
+            .enumerate()
+            .map(|(index, &min)| LengthBucket {
+                min,
+                max: BUCKET_STARTS.get(index + 1).map(|next| next - 1),
+                message_count: 0,
+            })
+            .collect();
+        let mut lengths: Vec<f64> = Vec::new();
+        let mut channels: Vec<ChannelLength> = Vec::new();
+        let mut dms: Vec<ChannelLength> = Vec::new();
+
+        for parsed in &extracted_data.channels {
+            if parsed.messages.is_empty() {
+                continue;
+            }
+            let channel_lengths: Vec<f64> =
+                parsed.messages.iter().map(|m| m.length as f64).collect();
+            for message in &parsed.messages {
+                if let Some(bucket) = histogram.iter_mut().rev().find(|b| message.length >= b.min) {
+                    bucket.message_count += 1;
+                }
+            }
+
+            let channel_type = parsed.channel.kind();
+            let entry = ChannelLength {
+                channel_id: parsed.channel.id.clone(),
+                name: parsed.name.clone(),
+                channel_type,
+                message_count: channel_lengths.len(),
+                average: channel_lengths.iter().sum::<f64>() / channel_lengths.len() as f64,
+                median: median(channel_lengths.clone()),
+            };
+            if channel_type.is_dm() {
+                dms.push(entry);
+            } else {
+                channels.push(entry);
+            }
+            lengths.extend(channel_lengths);
+        }
+
+        for list in [&mut channels, &mut dms] {
+            list.sort_by_key(|c| Reverse(c.message_count));
+            list.truncate(self.options.top_count);
+        }
+
+        extracted_data.lengths = LengthStats {
+            average: if lengths.is_empty() {
+                0.0
+            } else {
+                lengths.iter().sum::<f64>() / lengths.len() as f64
+            },
+            median: median(lengths.clone()),
+            p95: percentile(lengths, 95.0),
+            histogram,
+            channels,
+            dms,
+        };
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::fixtures::{data, dm_channel, guild_channel};
+
+    #[test]
+    fn buckets_lengths_and_splits_dms() {
+        let long = "x".repeat(2500);
+        let mut data = data(vec![
+            guild_channel(
+                "1",
+                "general",
+                &[
+                    ("2023-01-01T10:00:00Z", "a"),
+                    ("2023-01-01T10:01:00Z", "héllo"),
+                    ("2023-01-01T10:02:00Z", "exactly six"),
+                ],
+            ),
+            dm_channel(
+                "2",
+                "222222222222222222",
+                &[("2023-01-01T11:00:00Z", &long)],
+            ),
+        ]);
+        Parser::new().compute_length_stats(&mut data);
+
+        let lengths = &data.lengths;
+        let count = |min: usize| {
+            lengths
+                .histogram
+                .iter()
+                .find(|b| b.min == min)
+                .unwrap()
+                .message_count
+        };
+        assert_eq!((count(1), count(2), count(11), count(2001)), (1, 1, 1, 1));
+        assert_eq!(lengths.histogram.last().unwrap().max, None);
+        assert_eq!(lengths.median, 8.0);
+        assert_eq!(lengths.p95, 2500.0);
+        assert_eq!(lengths.channels.len(), 1);
+        assert_eq!(lengths.channels[0].median, 5.0);
+        assert_eq!(lengths.dms[0].average, 2500.0);
+    }
+
+    #[test]
+    fn bucket_edges_land_in_their_own_bucket() {
+        let contents: Vec<String> = BUCKET_STARTS
+            .iter()
+            .flat_map(|&min| [min - 1, min])
+            .filter(|&length| length > 0)
+            .map(|length| "x".repeat(length))
+            .collect();
+        let messages: Vec<(&str, &str)> = contents
+            .iter()
+            .map(|c| ("2023-01-01T10:00:00Z", c.as_str()))
+            .collect();
+        let mut data = data(vec![guild_channel("1", "general", &messages)]);
+        Parser::new().compute_length_stats(&mut data);
+
+        // Each bucket gets its lower and upper bound; the last one has no upper bound.
+        let counts: Vec<usize> = data
+            .lengths
+            .histogram
+            .iter()
+            .map(|b| b.message_count)
+            .collect();
+        let mut expected = vec![2; BUCKET_STARTS.len()];
+        expected[BUCKET_STARTS.len() - 1] = 1;
+        assert_eq!(counts, expected);
+        let first = &data.lengths.histogram[1];
+        assert_eq!((first.min, first.max), (2, Some(5)));
+    }
+
+    #[test]
+    fn empty_package_has_an_empty_histogram() {
+        let mut data = data(vec![guild_channel("1", "general", &[])]);
+        Parser::new().compute_length_stats(&mut data);
+
+        let lengths = &data.lengths;
+        assert_eq!(lengths.histogram.len(), BUCKET_STARTS.len());
+        assert!(lengths.histogram.iter().all(|b| b.message_count == 0));
+        assert_eq!(
+            (lengths.average, lengths.median, lengths.p95),
+            (0.0, 0.0, 0.0)
+        );
+        assert!(lengths.channels.is_empty());
+    }
+}
@@ -0,0 +1,448 @@
+use anyhow::{Result, anyhow};
+use chrono::{DateTime, Utc};
+use std::collections::{HashMap, HashSet};
+use std::io::{Read, Seek};
+use zip::ZipArchive;
+
+use crate::models::{ExtractedData, MergeReport, PackageContribution, ParsedChannel};
+use crate::parser::Parser;
+
+impl Parser {
+    /// Parses several packages of the same account and merges them into one [`ExtractedData`].
+    ///
+    /// Packages are merged oldest first, ordered by their newest message or analytics event:
+    /// account info and channel names from later packages win, while messages only present in
+    /// older packages are kept. Messages are deduplicated by id, and packages from a different
+    /// account are rejected.
+    pub fn merge_packages<R: Read + Seek, F>(
+        &mut self,
+        packages: Vec<(String, ZipArchive<R>)>,
+        progress_callback: F,
+    ) -> Result<(ExtractedData, MergeReport)>
+    where
+        F: Fn(String) + Send + Sync,
+    {
+        if packages.is_empty() {
+            return Err(anyhow!("No packages to merge"));
+        }
+
+        let mut merged = ExtractedData::default();
+        let mut report = MergeReport {
+            user_id: String::new(),
+            packages: Vec::new(),
+        };
+
+        let mut channel_positions: HashMap<String, usize> = HashMap::new();
+        let mut seen_messages: HashMap<String, HashSet<u64>> = HashMap::new();
+        let mut seen_payments: HashSet<String> = HashSet::new();
+        let mut seen_events: HashSet<(String, String, Option<String>)> = HashSet::new();
+        let mut payments = Vec::new();
+
+        let package_count = packages.len();
+        let mut parsed_packages = Vec::with_capacity(package_count);
+        for (index, (source, archive)) in packages.into_iter().enumerate() {
+            progress_callback(format!(
+                "Parsing package {} of {} ({})",
+                index + 1,
+                package_count,
+                source
+            ));
+            let data = self.parse_archive(archive, &progress_callback)?;
+            parsed_packages.push((Parser::package_date(&data), source, data));
+        }
+        // Stable, so packages without any dated activity keep their given order up front.
+        parsed_packages.sort_by_key(|(date, _, _)| *date);
+
+        for (package_date, source, data) in parsed_packages {
+            if let Some(user) = &data.user {
+                if report.user_id.is_empty() {
+                    report.user_id = user.id.clone();
+                } else if report.user_id != user.id {
+                    return Err(anyhow!(
+                        "Package {} belongs to user {}, expected {}",
+                        source,
+                        user.id,
+                        report.user_id
+                    ));
+                }
+            }
+
+            let mut contribution = PackageContribution {
+                source,
+                package_date,
+                channel_count: data.channels.len(),
+                message_count: data.channels.iter().map(|c| c.messages.len()).sum(),
+                new_channels: 0,
+                new_messages: 0,
+                duplicate_messages: 0,
+                new_guilds: 0,
+                new_payments: 0,
+            };
+
+            for parsed in data.channels {
+                let seen = seen_messages.entry(parsed.channel.id.clone()).or_default();
+                let (fresh, duplicates): (Vec<_>, Vec<_>) = parsed
+                    .messages
+                    .into_iter()
+                    .partition(|message| seen.insert(message.id));
+                contribution.new_messages += fresh.len();
+                contribution.duplicate_messages += duplicates.len();
+                let fresh_attachment_only: Vec<_> = parsed
+                    .attachment_only
+                    .into_iter()
+                    .filter(|message| seen.insert(message.id))
+                    .collect();
+
+                match channel_positions.get(&parsed.channel.id) {
+                    Some(&position) => {
+                        let existing = &mut merged.channels[position];
+                        existing.channel = parsed.channel;
+                        existing.name = parsed.name;
+                        existing.messages.extend(fresh);
+                        existing.attachment_only.extend(fresh_attachment_only);
+                    }
+                    None => {
+                        contribution.new_channels += 1;
+                        channel_positions.insert(parsed.channel.id.clone(), merged.channels.len());
+                        merged.channels.push(ParsedChannel {
+                            channel: parsed.channel,
+                            name: parsed.name,
+                            messages: fresh,
+                            attachment_only: fresh_attachment_only,
+                        });
+                    }
+                }
+            }
+
+            for (guild_id, guild_name) in data.guilds {
+                if merged.guilds.insert(guild_id, guild_name).is_none() {
+                    contribution.new_guilds += 1;
+                }
+            }
+
+            if let Some(user) = data.user {
+                for payment in &user.payments {
+                    if seen_payments.insert(Parser::payment_key(payment)) {
+                        payments.push(payment.clone());
+                        contribution.new_payments += 1;
+                    }
+                }
+                merged.user = Some(user);
+            }
+
+            for event in data.voice_events {
+                let key = (
+                    event.event_type.clone(),
+                    event.timestamp.clone(),
+                    event.channel_id.clone(),
+                );
+                if seen_events.insert(key) {
+                    merged.voice_events.push(event);
+                }
+            }
+            // Packages requested at different times log the same events for the days they
+            // share, so a day's count is the largest any package has rather than their sum.
+            for (date, counts) in data.analytics_daily_counts {
+                let merged_counts = merged.analytics_daily_counts.entry(date).or_default();
+                for (event_type, count) in counts {
+                    let merged_count = merged_counts.entry(event_type).or_insert(0);
+                    *merged_count = (*merged_count).max(count);
+                }
+            }
+
+            eprintln!(
+                "[debug] Package {}: {} new channels, {} new messages, {} duplicates",
+                contribution.source,
+                contribution.new_channels,
+                contribution.new_messages,
+                contribution.duplicate_messages
+            );
+            report.packages.push(contribution);
+        }
+
+        progress_callback("Merging packages...".to_string());
+
+        for parsed in &mut merged.channels {
+            parsed.messages.sort_by_key(|m| m.sent_at());
+            parsed.attachment_only.sort_by_key(|m| m.sent_at());
+        }
+
+        merged.guild_count = merged.guilds.len();
+        Parser::apply_event_counts(&mut merged);
+        merged.utc_offset_minutes = Parser::utc_offset_minutes(&merged.channels);
+        self.compute_voice_stats(&mut merged);
+        if let Some(mut user) = merged.user.take() {
+            user.payments = payments;
+            self.process_account(&mut merged, &user);
+            self.process_payments(&mut merged, &user);
+            merged.user = Some(user);
+        }
+        self.compute_channel_stats(&mut merged);
+
+        Ok((merged, report))
+    }
+
+    /// When a package was requested, as far as its contents tell: its newest message or
+    /// analytics event.
+    fn package_date(data: &ExtractedData) -> Option<DateTime<Utc>> {
+        let newest_message = data
+            .channels
+            .iter()
+            .flat_map(|c| &c.messages)
+            .filter_map(|m| m.sent_at())
+            .max();
+        let newest_event = data
+            .analytics_daily_counts
+            .last_key_value()
+            .and_then(|(date, _)| date.and_hms_opt(0, 0, 0))
+            .map(|date| date.and_utc());
+        newest_message.max(newest_event)
+    }
+
+    pub(super) fn payment_key(payment: &crate::models::Payment) -> String {
+        if payment.id.is_empty() {
+            format!(
+                "{}:{}:{}",
+                payment.created_at.to_rfc3339(),
+                payment.amount,
+                payment.description
+            )
+        } else {
+            payment.id.clone()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::fixtures::{USER_ID, package};
+    use serde_json::json;
+
+    fn payment(id: &str) -> serde_json::Value {
+        json!({
+            "id": id, "status": 1, "currency": "usd", "amount": 999, "amount_refunded": 0,
+            "created_at": "2023-01-05T10:00:00+00:00", "description": "Nitro Monthly",
+            "sku_id": "521842865731534868", "sku_price": 999, "flags": 0,
+        })
+    }
+
+    fn files(
+        user_id: &str,
+        payments: Vec<serde_json::Value>,
+        channels: &[(&str, &[u64])],
+        events: &[(&str, &str)],
+    ) -> Vec<(String, String)> {
+        let user = json!({
+            "id": user_id, "username": "tester", "discriminator": 0, "payments": payments,
+        });
+        let mut files = vec![("Account/user.json".to_string(), user.to_string())];
+        for (channel_id, ids) in channels {
+            let channel = json!({"id": channel_id, "type": 0, "name": "general",
+                "guild": {"id": "700000000000000001", "name": "Guild"}});
+            let messages: Vec<serde_json::Value> = ids
+                .iter()
+                .map(|id| {
+                    json!({"ID": id, "Timestamp": format!("2023-01-01T10:{:02}:00+00:00", id % 60),
+                        "Contents": format!("message {}", id), "Attachments": ""})
+                })
+                .collect();
+            let root = format!("Messages/c{}", channel_id);
+            files.push((format!("{}/channel.json", root), channel.to_string()));
+            files.push((
+                format!("{}/messages.json", root),
+                json!(messages).to_string(),
+            ));
+        }
+        let index: serde_json::Map<String, serde_json::Value> = channels
+            .iter()
+            .map(|(id, _)| (id.to_string(), json!("general in Guild")))
+            .collect();
+        files.push(("Messages/index.json".to_string(), json!(index).to_string()));
+        let events: Vec<String> = events
+            .iter()
+            .map(|(event_type, timestamp)| {
+                json!({"event_type": event_type, "timestamp": timestamp,
+                    "channel_id": "800000000000000005", "guild_id": "700000000000000001"})
+                .to_string()
+            })
+            .collect();
+        files.push((
+            "activity/analytics/events-2023-00000-of-00001.json".to_string(),
+            events.join("\n"),
+        ));
+        files
+    }
+
+    fn zip(files: Vec<(String, String)>) -> ZipArchive<std::io::Cursor<Vec<u8>>> {
+        let files: Vec<(&str, String)> = files
+            .iter()
+            .map(|(name, contents)| (name.as_str(), contents.clone()))
+            .collect();
+        package(&files)
+    }
+
+    #[test]
+    fn dedups_messages_payments_and_events() {
+        let older = files(
+            USER_ID,
+            vec![payment("1")],
+            &[("800000000000000001", &[1001, 1002])],
+            &[
+                ("app_opened", "2023-01-01T09:00:00Z"),
+                ("app_opened", "2023-01-01T09:30:00Z"),
+                ("join_voice_channel", "2023-01-01T20:00:00Z"),
+                ("leave_voice_channel", "2023-01-01T20:30:00Z"),
+            ],
+        );
+        let newer = files(
+            USER_ID,
+            vec![payment("1"), payment("2")],
+            &[
+                ("800000000000000001", &[1002, 1003]),
+                ("800000000000000002", &[1004]),
+            ],
+            &[
+                ("app_opened", "2023-01-01T09:00:00Z"),
+                ("join_voice_channel", "2023-01-01T20:00:00Z"),
+                ("leave_voice_channel", "2023-01-01T20:30:00Z"),
+                ("app_opened", "2023-01-02T09:00:00Z"),
+                ("join_voice_channel", "2023-01-02T20:00:00Z"),
+                ("leave_voice_channel", "2023-01-02T20:10:00Z"),
+            ],
+        );
+        let (merged, report) = Parser::new()
+            .merge_packages(
+                vec![
+                    ("old".to_string(), zip(older)),
+                    ("new".to_string(), zip(newer)),
+                ],
+                |_| {},
+            )
+            .unwrap();
+
+        assert_eq!(report.user_id, USER_ID);
+        let new = &report.packages[1];
+        assert_eq!(
+            (
+                new.new_channels,
+                new.new_messages,
+                new.duplicate_messages,
+                new.new_payments
+            ),
+            (1, 2, 1, 1)
+        );
+        let message_ids: Vec<u64> = merged.channels[0].messages.iter().map(|m| m.id).collect();
+        assert_eq!(message_ids, [1001, 1002, 1003]);
+        assert_eq!(merged.user.as_ref().unwrap().payments.len(), 2);
+
+        assert_eq!(merged.voice_events.len(), 4);
+        assert_eq!(merged.voice.session_count, 2);
+        assert_eq!(merged.voice.total_minutes, 40.0);
+        // The first day's count is the larger of the two packages', not their sum.
+        assert_eq!(merged.open_count, Some(3));
+    }
+
+    #[test]
+    fn sorts_packages_given_out_of_order() {
+        let older = files(USER_ID, Vec::new(), &[("800000000000000001", &[1001])], &[]);
+        let newer = files(
+            USER_ID,
+            Vec::new(),
+            &[("800000000000000001", &[1001, 1005])],
+            &[],
+        );
+        let (merged, report) = Parser::new()
+            .merge_packages(
+                vec![
+                    ("new".to_string(), zip(newer)),
+                    ("old".to_string(), zip(older)),
+                ],
+                |_| {},
+            )
+            .unwrap();
+
+        let order: Vec<&str> = report.packages.iter().map(|p| p.source.as_str()).collect();
+        assert_eq!(order, ["old", "new"]);
+        assert!(report.packages[0].package_date < report.packages[1].package_date);
+        assert_eq!(
+            (report.packages[1].new_messages, merged.message_count),
+            (1, 2)
+        );
+    }
+
+    #[test]
+    fn packages_without_messages_merge_first() {
+        let empty = files(USER_ID, Vec::new(), &[("800000000000000002", &[])], &[]);
+        let full = files(USER_ID, Vec::new(), &[("800000000000000001", &[1001])], &[]);
+        let (merged, report) = Parser::new()
+            .merge_packages(
+                vec![
+                    ("full".to_string(), zip(full)),
+                    ("empty".to_string(), zip(empty)),
+                ],
+                |_| {},
+            )
+            .unwrap();
+        assert_eq!(report.packages[0].source, "empty");
+        assert_eq!(report.packages[0].package_date, None);
+        assert_eq!(merged.message_count, 1);
+    }
+
+    #[test]
+    fn single_package_merges_as_is() {
+        let only = files(USER_ID, Vec::new(), &[("800000000000000001", &[1001])], &[]);
+        let (merged, report) = Parser::new()
+            .merge_packages(vec![("only".to_string(), zip(only))], |_| {})
+            .unwrap();
+        assert_eq!(report.packages.len(), 1);
+        let only = &report.packages[0];
+        assert_eq!((only.new_messages, only.duplicate_messages), (1, 0));
+        assert_eq!(merged.message_count, 1);
+        assert_eq!(merged.activity.longest_streak.unwrap().days, 1);
+    }
+
+    #[test]
+    fn empty_packages_merge_to_empty_stats() {
+        let first = files(USER_ID, Vec::new(), &[("800000000000000001", &[])], &[]);
+        let second = files(USER_ID, Vec::new(), &[("800000000000000001", &[])], &[]);
+        let (merged, report) = Parser::new()
+            .merge_packages(
+                vec![
+                    ("first".to_string(), zip(first)),
+                    ("second".to_string(), zip(second)),
+                ],
+                |_| {},
+            )
+            .unwrap();
+        assert!(report.packages.iter().all(|p| p.package_date.is_none()));
+        assert_eq!(merged.message_count, 0);
+        assert!(merged.daily_values.is_empty());
+        assert!(merged.activity.busiest_day.is_none());
+    }
+
+    #[test]
+    fn rejects_no_packages() {
+        let packages: Vec<(String, ZipArchive<std::io::Cursor<Vec<u8>>>)> = Vec::new();
+        assert!(Parser::new().merge_packages(packages, |_| {}).is_err());
+    }
+
+    #[test]
+    fn rejects_packages_of_another_account() {
+        let first = files(USER_ID, Vec::new(), &[("800000000000000001", &[1001])], &[]);
+        let second = files(
+            "999999999999999999",
+            Vec::new(),
+            &[("800000000000000001", &[1001])],
+            &[],
+        );
+        let result = Parser::new().merge_packages(
+            vec![
+                ("a".to_string(), zip(first)),
+                ("b".to_string(), zip(second)),
+            ],
+            |_| {},
+        );
+        assert!(result.is_err());
+    }
+}
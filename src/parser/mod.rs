@@ -1,28 +1,126 @@
+mod activity;
 mod analytics;
 mod channels;
+mod content;
+mod diff;
+#[cfg(test)]
+pub(crate) mod fixtures;
+mod language;
+mod length;
+mod merge;
+mod payments;
 mod servers;
 mod user;
 mod utils;
+mod voice;
+mod words;
+mod wrapped;
 
 use anyhow::Result;
 use std::collections::HashMap;
-use std::io::Read;
+use std::fs::File;
+use std::io::{BufReader, Read, Seek};
+use std::path::Path;
 use zip::ZipArchive;
 
 use crate::models::ExtractedData;
 
+/// Tuning knobs for [`Parser`]. The defaults match what the viewer shows.
+#[derive(Debug, Clone)]
+#[non_exhaustive]
+pub struct ParserOptions {
+    /// How many entries to keep in the top channels, top DMs and favorite words rankings.
+    pub top_count: usize,
+    /// Words shorter than this many characters are left out of the favorite words.
+    pub min_word_length: usize,
+    /// Whether to read the (often very large) analytics event logs.
+    pub load_analytics: bool,
+    /// Messages further apart than this many minutes belong to different sessions.
+    pub session_gap_minutes: i64,
+}
+
+impl Default for ParserOptions {
+    fn default() -> Self {
+        Self {
+            top_count: 10,
+            min_word_length: 6,
+            load_analytics: true,
+            session_gap_minutes: 30,
+        }
+    }
+}
+
+impl ParserOptions {
+    pub fn top_count(mut self, top_count: usize) -> Self {
+        self.top_count = top_count;
+        self
+    }
+
+    pub fn min_word_length(mut self, min_word_length: usize) -> Self {
+        self.min_word_length = min_word_length;
+        self
+    }
+
+    pub fn load_analytics(mut self, load_analytics: bool) -> Self {
+        self.load_analytics = load_analytics;
+        self
+    }
+
+    pub fn session_gap_minutes(mut self, session_gap_minutes: i64) -> Self {
+        self.session_gap_minutes = session_gap_minutes;
+        self
+    }
+
+    /// Identifies the options in the cache, so results parsed with other options aren't reused.
+    pub(crate) fn fingerprint(&self) -> String {
+        format!(
+            "top_count={};min_word_length={};load_analytics={};session_gap_minutes={}",
+            self.top_count, self.min_word_length, self.load_analytics, self.session_gap_minutes
+        )
+    }
+}
+
+/// Reads a Discord data package and turns it into [`ExtractedData`].
+///
+/// A parser can be reused for several packages; each call starts from a clean state.
 pub struct Parser {
     pub(crate) file_index: HashMap<String, usize>,
+    pub(crate) options: ParserOptions,
+}
+
+impl Default for Parser {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl Parser {
     pub fn new() -> Self {
+        Self::with_options(ParserOptions::default())
+    }
+
+    pub fn with_options(options: ParserOptions) -> Self {
         Self {
             file_index: HashMap::new(),
+            options,
         }
     }
 
-    pub async fn extract_data<R: Read + std::io::Seek, F>(
+    pub fn options(&self) -> &ParserOptions {
+        &self.options
+    }
+
+    /// Parses the package zip at `path`.
+    pub fn parse_file(&mut self, path: impl AsRef<Path>) -> Result<ExtractedData> {
+        let archive = ZipArchive::new(BufReader::new(File::open(path)?))?;
+        self.parse_archive(archive, |_| {})
+    }
+
+    /// Parses an already opened package, reporting progress through `progress_callback`.
+    ///
+    /// Fails if the archive is not a data package; missing optional sections
+    /// (servers, analytics, avatar) are skipped.
+    pub fn parse_archive<R: Read + Seek, F>(
         &mut self,
         mut archive: ZipArchive<R>,
         progress_callback: F,
@@ -34,9 +132,11 @@ impl Parser {
 
         progress_callback("Analyzing package structure...".to_string());
 
-        self.file_index = (0..archive.len())
-            .map(|i| (archive.by_index(i).unwrap().name().to_string(), i))
-            .collect();
+        self.file_index = HashMap::with_capacity(archive.len());
+        for i in 0..archive.len() {
+            let name = archive.by_index_raw(i)?.name().to_string();
+            self.file_index.insert(name, i);
+        }
 
         let file_names: Vec<&String> = self.file_index.keys().collect();
 
@@ -44,17 +144,16 @@ impl Parser {
         let servers_root = Parser::get_servers_root(&file_names)?;
         let user_root = Parser::get_user_root(&file_names)?;
 
-        println!("[debug] Found messages root: {}", messages_root);
-        println!("[debug] Found servers root: {}", servers_root);
-        println!("[debug] Found user root: {}", user_root);
+        eprintln!("[debug] Found messages root: {}", messages_root);
+        eprintln!("[debug] Found servers root: {}", servers_root);
+        eprintln!("[debug] Found user root: {}", user_root);
 
         self.load_user(
             &mut archive,
             &user_root,
             &mut extracted_data,
             &progress_callback,
-        )
-        .await?;
+        )?;
         self.load_channels(
             &mut archive,
             &messages_root,
@@ -67,15 +166,29 @@ impl Parser {
             &mut extracted_data,
             &progress_callback,
         )?;
-        self.load_analytics(
-            &mut archive,
-            &file_names,
-            &mut extracted_data,
-            &progress_callback,
-        )?;
+        if self.options.load_analytics {
+            self.load_analytics(
+                &mut archive,
+                &file_names,
+                &mut extracted_data,
+                &progress_callback,
+            )?;
+        }
 
         progress_callback("Finalizing extraction...".to_string());
-        println!("[debug] Extraction complete");
+        eprintln!("[debug] Extraction complete");
         Ok(extracted_data)
     }
+
+    /// Async flavour of [`Parser::parse_archive`], kept for callers running inside a runtime.
+    pub async fn extract_data<R: Read + Seek, F>(
+        &mut self,
+        archive: ZipArchive<R>,
+        progress_callback: F,
+    ) -> Result<ExtractedData>
+    where
+        F: Fn(String) + Send + Sync,
+    {
+        self.parse_archive(archive, progress_callback)
+    }
 }
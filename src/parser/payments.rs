@@ -0,0 +1,168 @@
+use chrono::{DateTime, Datelike, Duration, Months, Utc};
+use std::collections::HashMap;
+
+use crate::currency::{self, ExchangeRates};
+use crate::models::{ExtractedData, Payment, PaymentStatus, SubscriptionPeriod, User};
+use crate::parser::Parser;
+
+/// A renewal this late after the previous period ran out still counts as the same subscription.
+const RENEWAL_GRACE_DAYS: i64 = 7;
+
+impl Parser {
+    pub(super) fn process_payments(&self, extracted_data: &mut ExtractedData, user: &User) {
+        let info = &mut extracted_data.payments;
+        *info = Default::default();
+
+        for payment in &user.payments {
+            match payment.status {
+                PaymentStatus::Completed => info.completed_count += 1,
+                PaymentStatus::Failed => info.failed_count += 1,
+                _ => {}
+            }
+            if payment.is_refunded() {
+                info.refunded_count += 1;
+                let refunded = match payment.status {
+                    PaymentStatus::Completed => payment.amount_refunded,
+                    _ => payment.amount,
+                };
+                *info.refunded.entry(payment.currency.clone()).or_insert(0.0) +=
+                    refunded as f64 / 100.0;
+            }
+
+            if payment.status != PaymentStatus::Completed {
+                continue;
+            }
+            let net = payment.net_amount();
+            *info.total.entry(payment.currency.clone()).or_insert(0.0) += net as f64 / 100.0;
+            *info
+                .by_year
+                .entry(payment.created_at.year())
+                .or_default()
+                .entry(payment.currency.clone())
+                .or_insert(0.0) += net as f64 / 100.0;
+        }
+
+        info.subscriptions = Parser::subscription_periods(&user.payments);
+
+        let spending = currency::spending(&user.payments, ExchangeRates::bundled(), None);
+        currency::apply_spending(extracted_data, spending);
+    }
+
+    /// Groups completed Nitro payments per plan into periods of uninterrupted renewals.
+    fn subscription_periods(payments: &[Payment]) -> Vec<SubscriptionPeriod> {
+        let mut by_plan: HashMap<&str, Vec<&Payment>> = HashMap::new();
+        for payment in payments {
+            if payment.is_nitro() && payment.net_amount() > 0 {
+                by_plan.entry(&payment.description).or_default().push(payment);
+            }
+        }
+
+        let mut periods: Vec<SubscriptionPeriod> = Vec::new();
+        for (description, mut plan_payments) in by_plan {
+            plan_payments.sort_by_key(|p| p.created_at);
+            let months = if description.to_lowercase().contains("year") { 12 } else { 1 };
+            let mut current: Option<SubscriptionPeriod> = None;
+            for payment in plan_payments {
+                let end = Parser::add_months(payment.created_at, months);
+                match current.as_mut() {
+                    Some(period)
+                        if payment.created_at
+                            <= period.end + Duration::days(RENEWAL_GRACE_DAYS) =>
+                    {
+                        period.end = end.max(period.end);
+                        period.payment_count += 1;
+                    }
+                    _ => {
+                        periods.extend(current.take());
+                        current = Some(SubscriptionPeriod {
+                            description: description.to_string(),
+                            sku_id: payment.sku_id.clone(),
+                            start: payment.created_at,
+                            end,
+                            payment_count: 1,
+                        });
+                    }
+                }
+            }
+            periods.extend(current);
+        }
+        periods.sort_by_key(|p| p.start);
+        periods
+    }
+
+    fn add_months(date: DateTime<Utc>, months: u32) -> DateTime<Utc> {
+        date.checked_add_months(Months::new(months))
+            .unwrap_or(date + Duration::days(30 * months as i64))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::fixtures::{data, user};
+    use serde_json::json;
+
+    fn payment(id: &str, status: i32, created_at: &str, description: &str) -> serde_json::Value {
+        json!({
+            "id": id, "status": status, "currency": "usd", "amount": 999,
+            "amount_refunded": 0, "created_at": created_at, "description": description,
+        })
+    }
+
+    #[test]
+    fn groups_renewals_into_periods() {
+        let user = user(json!({"payments": [
+            payment("1", 1, "2023-01-05T10:00:00Z", "Nitro Monthly"),
+            payment("2", 1, "2023-02-05T10:00:00Z", "Nitro Monthly"),
+            // Within the grace period after the previous month ran out.
+            payment("3", 1, "2023-03-10T10:00:00Z", "Nitro Monthly"),
+            payment("4", 2, "2023-04-10T10:00:00Z", "Nitro Monthly"),
+            payment("5", 1, "2023-06-01T10:00:00Z", "Nitro Monthly"),
+            payment("6", 1, "2023-07-01T10:00:00Z", "Nitro Yearly"),
+            payment("7", 1, "2023-07-02T10:00:00Z", "Server Boost"),
+        ]}));
+        let periods = Parser::subscription_periods(&user.payments);
+        let summary: Vec<(&str, String, String, usize)> = periods
+            .iter()
+            .map(|p| {
+                (
+                    p.description.as_str(),
+                    p.start.date_naive().to_string(),
+                    p.end.date_naive().to_string(),
+                    p.payment_count,
+                )
+            })
+            .collect();
+        assert_eq!(
+            summary,
+            [
+                ("Nitro Monthly", "2023-01-05".into(), "2023-04-10".into(), 3),
+                ("Nitro Monthly", "2023-06-01".into(), "2023-07-01".into(), 1),
+                ("Nitro Yearly", "2023-07-01".into(), "2024-07-01".into(), 1),
+            ]
+        );
+    }
+
+    #[test]
+    fn totals_count_refunds_and_statuses() {
+        let mut refunded = payment("3", 1, "2024-01-05T10:00:00Z", "Nitro Monthly");
+        refunded["amount_refunded"] = json!(999);
+        let user = user(json!({"payments": [
+            payment("1", 1, "2023-01-05T10:00:00Z", "Nitro Monthly"),
+            payment("2", 2, "2023-02-05T10:00:00Z", "Nitro Monthly"),
+            refunded,
+            payment("4", 4, "2024-02-05T10:00:00Z", "Nitro Monthly"),
+        ]}));
+        let mut data = data(Vec::new());
+        Parser::new().process_payments(&mut data, &user);
+
+        let info = &data.payments;
+        assert_eq!(info.completed_count, 2);
+        assert_eq!(info.failed_count, 1);
+        assert_eq!(info.refunded_count, 2);
+        assert_eq!(info.total["usd"], 9.99);
+        assert_eq!(info.refunded["usd"], 19.98);
+        assert_eq!(info.by_year[&2023]["usd"], 9.99);
+        assert_eq!(info.by_year[&2024]["usd"], 0.0);
+    }
+}
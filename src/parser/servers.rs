@@ -22,11 +22,14 @@ impl Parser {
         let guild_index_path = format!("{}/index.json", servers_root);
         
         if let Some(content) = self.read_file(archive, &guild_index_path)? {
-            println!("[debug] Loading guild index from: {}", guild_index_path);
+            eprintln!("[debug] Loading guild index from: {}", guild_index_path);
             
             match self.parse_json::<HashMap<String, String>>(&content) {
-                Ok(guild_index) => { extracted_data.guild_count = guild_index.len(); }
-                Err(e) => println!("[debug] Failed to parse guild index: {}", e),
+                Ok(guild_index) => {
+                    extracted_data.guild_count = guild_index.len();
+                    extracted_data.guilds = guild_index;
+                }
+                Err(e) => eprintln!("[debug] Failed to parse guild index: {}", e),
             }
         }
         
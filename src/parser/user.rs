@@ -2,11 +2,11 @@ use anyhow::Result;
 use std::io::Read;
 use zip::ZipArchive;
 
-use crate::models::{ExtractedData, Payment, User};
+use crate::models::{ExtractedData, User, default_avatar_index};
 use crate::parser::Parser;
 
 impl Parser {
-    pub(super) async fn load_user<R: Read + std::io::Seek, F>(
+    pub(super) fn load_user<R: Read + std::io::Seek, F>(
         &self,
         archive: &mut ZipArchive<R>,
         user_root: &str,
@@ -21,18 +21,19 @@ impl Parser {
         let user_path = format!("{}/user.json", user_root);
 
         if let Some(content) = self.read_file(archive, &user_path)? {
-            println!("[debug] Loading user info from: {}", user_path);
+            eprintln!("[debug] Loading user info from: {}", user_path);
 
             if let Ok(mut user) = self.parse_json::<User>(&content) {
-                user.avatar = self.load_user_avatar(archive, user_root)?;
-                user.default_avatar_url = Some(self.get_default_avatar_url(
-                    &user.id,
-                    user.discriminator,
-                ));
+                if let Some((avatar, extension)) = self.load_user_avatar(archive, user_root)? {
+                    user.avatar = Some(avatar);
+                    user.avatar_extension = Some(extension);
+                }
+                user.default_avatar_index = default_avatar_index(&user.id, user.discriminator);
+                self.process_account(extracted_data, &user);
                 self.process_payments(extracted_data, &user);
                 extracted_data.user = Some(user);
             } else {
-                println!("[debug] Failed to parse user.json");
+                eprintln!("[debug] Failed to parse user.json");
             }
         }
 
@@ -43,8 +44,8 @@ impl Parser {
         &self,
         archive: &mut ZipArchive<R>,
         user_root: &str,
-    ) -> Result<Option<Vec<u8>>> {
-        let extensions = ["png", "jpeg", "jpg", "gif"];
+    ) -> Result<Option<(Vec<u8>, String)>> {
+        let extensions = ["png", "jpeg", "jpg", "gif", "webp"];
 
         for ext in &extensions {
             let avatar_path = format!("{}/avatar.{}", user_root, ext);
@@ -52,53 +53,25 @@ impl Parser {
                 continue;
             }
             if let Some(content) = self.read_binary_file(archive, &avatar_path)? {
-                println!("[debug] Found avatar: {}", avatar_path);
-                return Ok(Some(content));
+                eprintln!("[debug] Found avatar: {}", avatar_path);
+                return Ok(Some((content, ext.to_string())));
             }
         }
 
-        println!("[debug] No avatar found in {}", user_root);
+        eprintln!("[debug] No avatar found in {}", user_root);
         Ok(None)
     }
 
-    fn get_default_avatar_url(
-        &self,
-        user_id: &str,
-        discriminator: u16
-    ) -> String {
-        let avatar_index = if discriminator == 0 {
-            ((user_id.parse::<u64>().unwrap_or(0) >> 22) % 6) as u16
-        } else {
-            discriminator % 5
-        };
-        format!("https://cdn.discordapp.com/embed/avatars/{}.png", avatar_index)
-    }
-
-    fn process_payments(&self, extracted_data: &mut ExtractedData, user: &User) {
-        let confirmed: Vec<&Payment> = user.payments.iter().filter(|p| p.status == 1).collect();
-        if confirmed.is_empty() {
-            return;
-        }
-        for payment in &confirmed {
-            *extracted_data
-                .payments
-                .total
-                .entry(payment.currency.clone())
-                .or_insert(0.0) += payment.amount as f64 / 100.0;
-        }
-        let mut sorted = confirmed;
-        sorted.sort_by(|a, b| a.created_at.cmp(&b.created_at));
-        extracted_data.payments.list = sorted
-            .iter()
-            .map(|p| {
-                format!(
-                    "{} ({} {:.2})",
-                    p.description,
-                    p.currency.to_uppercase(),
-                    p.amount as f64 / 100.0
-                )
-            })
-            .collect::<Vec<_>>()
-            .join("<br>");
+    pub(super) fn process_account(&self, extracted_data: &mut ExtractedData, user: &User) {
+        extracted_data.account_created_at = user.created_at();
+        extracted_data.friend_count = user.friend_count();
+        extracted_data.blocked_count = user.blocked_count();
+        extracted_data.linked_services = user.linked_services();
+        eprintln!(
+            "[debug] {} friends, {} blocked, {} linked services",
+            extracted_data.friend_count,
+            extracted_data.blocked_count,
+            extracted_data.linked_services.len()
+        );
     }
 }
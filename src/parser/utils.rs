@@ -20,7 +20,7 @@ impl Parser {
             reader.read_to_string(&mut content)?;
             let cleaned_content = content.trim_start_matches('\u{FEFF}').trim();
             if cleaned_content.is_empty() {
-                println!("[debug] Warning: File {} is empty", path);
+                eprintln!("[debug] Warning: File {} is empty", path);
                 return Ok(None);
             }
             return Ok(Some(cleaned_content.to_string()));
@@ -38,7 +38,7 @@ impl Parser {
             let mut buffer = Vec::new();
             file.read_to_end(&mut buffer)?;
             if buffer.is_empty() {
-                println!("[debug] Warning: Binary file {} is empty", path);
+                eprintln!("[debug] Warning: Binary file {} is empty", path);
                 return Ok(None);
             }
             return Ok(Some(buffer));
@@ -60,17 +60,17 @@ impl Parser {
         let mut data = content.as_bytes().to_vec();
         match simd_json::from_slice::<T>(&mut data) {
             Ok(result) => {
-                println!("[debug] Successfully parsed with simd_json");
+                eprintln!("[debug] Successfully parsed with simd_json");
                 Ok(result)
             }
             Err(e) => {
-                println!(
+                eprintln!(
                     "[debug] simd_json failed: {}, falling back to serde_json",
                     e
                 );
                 match serde_json::from_str::<T>(content) {
                     Ok(result) => {
-                        println!("[debug] Successfully parsed with serde_json");
+                        eprintln!("[debug] Successfully parsed with serde_json");
                         Ok(result)
                     }
                     Err(e2) => Err(anyhow!(
@@ -90,6 +90,12 @@ impl Parser {
         content.split_whitespace().map(|s| s.to_string()).collect()
     }
 
+    pub(crate) fn process_attachments(attachments: Option<&str>) -> Vec<String> {
+        attachments
+            .map(|a| a.split_whitespace().map(|s| s.to_string()).collect())
+            .unwrap_or_default()
+    }
+
     pub(crate) fn get_messages_root(files: &[&String]) -> Result<String> {
         let regex = Regex::new(r"/c?[0-9]{16,32}/channel\.json$")?;
         let sample = files
@@ -120,3 +126,48 @@ impl Parser {
         Ok(segments[..segments.len() - 1].join("/"))
     }
 }
+
+pub(crate) fn median(mut values: Vec<f64>) -> f64 {
+    if values.is_empty() {
+        return 0.0;
+    }
+    values.sort_by(f64::total_cmp);
+    let mid = values.len() / 2;
+    if values.len().is_multiple_of(2) {
+        (values[mid - 1] + values[mid]) / 2.0
+    } else {
+        values[mid]
+    }
+}
+
+/// Nearest-rank percentile of `values`, with `percent` from 0 to 100.
+pub(crate) fn percentile(mut values: Vec<f64>, percent: f64) -> f64 {
+    if values.is_empty() {
+        return 0.0;
+    }
+    values.sort_by(f64::total_cmp);
+    let rank = (percent / 100.0 * values.len() as f64).ceil() as usize;
+    values[rank.clamp(1, values.len()) - 1]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn median_averages_the_middle_pair() {
+        assert_eq!(median(vec![3.0, 1.0, 2.0]), 2.0);
+        assert_eq!(median(vec![4.0, 1.0, 3.0, 2.0]), 2.5);
+        assert_eq!(median(Vec::new()), 0.0);
+    }
+
+    #[test]
+    fn percentile_uses_the_nearest_rank() {
+        let values: Vec<f64> = (1..=20).map(f64::from).collect();
+        assert_eq!(percentile(values.clone(), 95.0), 19.0);
+        assert_eq!(percentile(values.clone(), 100.0), 20.0);
+        assert_eq!(percentile(values, 0.0), 1.0);
+        assert_eq!(percentile(vec![7.0], 95.0), 7.0);
+        assert_eq!(percentile(Vec::new(), 95.0), 0.0);
+    }
+}
@@ -0,0 +1,312 @@
+use chrono::{DateTime, Duration, DurationRound, FixedOffset, Timelike, Utc};
+use std::collections::HashMap;
+
+use crate::models::{
+    AnalyticsEvent, ExtractedData, VoiceSession, VoiceSessionKind, VoiceStats, VoiceTime,
+};
+use crate::parser::Parser;
+
+/// A session without a leave event is closed by the next join; anything longer than this is
+/// more likely a lost leave event than a real session, and is dropped.
+const MAX_SESSION_HOURS: i64 = 12;
+
+enum VoiceEvent {
+    Join(VoiceSessionKind),
+    Leave,
+}
+
+fn voice_event(event_type: &str) -> Option<VoiceEvent> {
+    match event_type {
+        "join_voice_channel" => Some(VoiceEvent::Join(VoiceSessionKind::VoiceChannel)),
+        "join_call" | "start_call" => Some(VoiceEvent::Join(VoiceSessionKind::Call)),
+        "leave_voice_channel" | "leave_call" | "call_ended" | "voice_disconnect" => {
+            Some(VoiceEvent::Leave)
+        }
+        _ => None,
+    }
+}
+
+struct OpenSession<'a> {
+    kind: VoiceSessionKind,
+    event: &'a AnalyticsEvent,
+    start: DateTime<Utc>,
+}
+
+impl Parser {
+    pub(super) fn is_voice_event(event_type: &str) -> bool {
+        voice_event(event_type).is_some()
+    }
+
+    /// Pairs the join and leave events of the analytics logs into voice sessions. Discord only
+    /// keeps one voice connection open at a time, so a join or leave while a session is open
+    /// ends it.
+    pub(super) fn compute_voice_stats(&self, extracted_data: &mut ExtractedData) {
+        let mut events: Vec<(DateTime<Utc>, VoiceEvent, &AnalyticsEvent)> = extracted_data
+            .voice_events
+            .iter()
+            .filter_map(|event| {
+                let kind = voice_event(&event.event_type)?;
+                let timestamp = DateTime::parse_from_rfc3339(&event.timestamp).ok()?;
+                Some((timestamp.with_timezone(&Utc), kind, event))
+            })
+            .collect();
+        events.sort_by_key(|(timestamp, _, _)| *timestamp);
+
+        let max_session = Duration::hours(MAX_SESSION_HOURS);
+        let mut sessions: Vec<(OpenSession, DateTime<Utc>)> = Vec::new();
+        let mut unmatched = 0;
+        let mut open: Option<OpenSession> = None;
+        for (timestamp, kind, event) in events {
+            match kind {
+                VoiceEvent::Join(kind) => {
+                    if let Some(current) = &open
+                        && current.kind == VoiceSessionKind::Call
+                        && kind == VoiceSessionKind::Call
+                        && current.event.channel_id == event.channel_id
+                    {
+                        // `start_call` is usually followed by a `join_call` for the same call.
+                        continue;
+                    }
+                    if let Some(current) = open.take() {
+                        sessions.push((current, timestamp));
+                    }
+                    open = Some(OpenSession {
+                        kind,
+                        event,
+                        start: timestamp,
+                    });
+                }
+                // Only one connection is open at a time, so any leave ends the open session,
+                // even one logged with a different channel id.
+                VoiceEvent::Leave => match open.take() {
+                    Some(current) => sessions.push((current, timestamp)),
+                    None => unmatched += 1,
+                },
+            }
+        }
+        if open.is_some() {
+            unmatched += 1;
+        }
+
+        let channel_names: HashMap<&str, &str> = extracted_data
+            .channels
+            .iter()
+            .map(|p| (p.channel.id.as_str(), p.name.as_str()))
+            .collect();
+        // Guilds that were left are missing from the server index, but their channels still
+        // record the guild name.
+        let mut guild_names: HashMap<&str, &str> = extracted_data
+            .channels
+            .iter()
+            .filter_map(|p| p.channel.guild.as_ref())
+            .map(|guild| (guild.id.as_str(), guild.name.as_str()))
+            .collect();
+        guild_names.extend(
+            extracted_data
+                .guilds
+                .iter()
+                .map(|(id, name)| (id.as_str(), name.as_str())),
+        );
+        let offset = extracted_data.utc_offset();
+        let mut stats = VoiceStats::default();
+        let mut guilds: HashMap<&str, VoiceTime> = HashMap::new();
+        let mut channels: HashMap<&str, VoiceTime> = HashMap::new();
+        for (session, end) in sessions {
+            let length = end - session.start;
+            if length <= Duration::zero() || length > max_session {
+                unmatched += 1;
+                continue;
+            }
+            let minutes = length.num_seconds() as f64 / 60.0;
+            stats.session_count += 1;
+            stats.total_minutes += minutes;
+            add_hour_minutes(
+                &mut stats.hours_minutes,
+                session.start.with_timezone(&offset),
+                end.with_timezone(&offset),
+            );
+
+            let channel_id = session.event.channel_id.as_deref();
+            let guild_id = session.event.guild_id.as_deref();
+            let channel_name = channel_id
+                .and_then(|id| channel_names.get(id))
+                .map(|name| name.to_string());
+            let guild_name = guild_id
+                .and_then(|id| guild_names.get(id))
+                .map(|name| name.to_string());
+            for (map, id, name) in [
+                (&mut channels, channel_id, &channel_name),
+                (&mut guilds, guild_id, &guild_name),
+            ] {
+                let Some(id) = id else {
+                    continue;
+                };
+                let entry = map.entry(id).or_insert_with(|| VoiceTime {
+                    id: id.to_string(),
+                    name: name.clone(),
+                    session_count: 0,
+                    minutes: 0.0,
+                });
+                entry.session_count += 1;
+                entry.minutes += minutes;
+            }
+
+            if stats
+                .longest_session
+                .as_ref()
+                .is_none_or(|longest| minutes > longest.minutes)
+            {
+                stats.longest_session = Some(VoiceSession {
+                    kind: session.kind,
+                    channel_id: channel_id.map(str::to_string),
+                    channel_name,
+                    guild_id: guild_id.map(str::to_string),
+                    guild_name,
+                    start: session.start,
+                    end,
+                    minutes,
+                });
+            }
+        }
+
+        let ranked = |map: HashMap<&str, VoiceTime>| {
+            let mut list: Vec<VoiceTime> = map.into_values().collect();
+            list.sort_by(|a, b| {
+                b.minutes
+                    .total_cmp(&a.minutes)
+                    .then_with(|| a.id.cmp(&b.id))
+            });
+            list.truncate(self.options.top_count);
+            list
+        };
+        stats.guilds = ranked(guilds);
+        stats.channels = ranked(channels);
+        stats.unmatched_event_count = unmatched;
+        if unmatched > 0 {
+            eprintln!("[debug] Skipped {} unmatched voice events", unmatched);
+        }
+
+        extracted_data.voice = stats;
+    }
+}
+
+/// Spreads the minutes between `start` and `end` over the hours of the day they fall in.
+fn add_hour_minutes(hours: &mut [f64], start: DateTime<FixedOffset>, end: DateTime<FixedOffset>) {
+    let mut cursor = start;
+    while cursor < end {
+        let next_hour = cursor
+            .duration_trunc(Duration::hours(1))
+            .map(|hour| hour + Duration::hours(1))
+            .unwrap_or(end);
+        let until = next_hour.min(end);
+        hours[cursor.hour() as usize] += (until - cursor).num_seconds() as f64 / 60.0;
+        cursor = until;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn event(event_type: &str, timestamp: &str, channel_id: Option<&str>) -> AnalyticsEvent {
+        AnalyticsEvent {
+            event_type: event_type.to_string(),
+            timestamp: timestamp.to_string(),
+            channel_id: channel_id.map(str::to_string),
+            guild_id: None,
+        }
+    }
+
+    fn voice(events: Vec<AnalyticsEvent>) -> VoiceStats {
+        let mut data = ExtractedData {
+            voice_events: events,
+            ..ExtractedData::default()
+        };
+        Parser::new().compute_voice_stats(&mut data);
+        data.voice
+    }
+
+    #[test]
+    fn pairs_joins_with_leaves() {
+        let stats = voice(vec![
+            event("leave_voice_channel", "2023-01-01T09:00:00Z", Some("1")),
+            event("join_voice_channel", "2023-01-01T10:00:00Z", Some("1")),
+            event("leave_voice_channel", "2023-01-01T10:30:00Z", Some("1")),
+            // A join while connected ends the open session.
+            event("join_voice_channel", "2023-01-01T11:00:00Z", Some("1")),
+            event("join_voice_channel", "2023-01-01T11:15:00Z", Some("2")),
+            event("voice_disconnect", "2023-01-01T11:20:00Z", None),
+        ]);
+        assert_eq!(stats.session_count, 3);
+        assert_eq!(stats.total_minutes, 50.0);
+        assert_eq!(stats.unmatched_event_count, 1);
+        assert_eq!(stats.channels[0].id, "1");
+        assert_eq!(stats.channels[0].minutes, 45.0);
+        assert_eq!(stats.longest_session.unwrap().minutes, 30.0);
+    }
+
+    #[test]
+    fn leave_for_another_channel_closes_the_session() {
+        let stats = voice(vec![
+            event("join_voice_channel", "2023-01-01T10:00:00Z", Some("1")),
+            event("leave_voice_channel", "2023-01-01T10:20:00Z", Some("2")),
+            event("join_voice_channel", "2023-01-01T15:00:00Z", Some("1")),
+            event("leave_voice_channel", "2023-01-01T15:10:00Z", Some("1")),
+        ]);
+        assert_eq!(stats.session_count, 2);
+        assert_eq!(stats.total_minutes, 30.0);
+        assert_eq!(stats.unmatched_event_count, 0);
+    }
+
+    #[test]
+    fn start_call_and_join_call_are_one_session() {
+        let stats = voice(vec![
+            event("start_call", "2023-01-01T10:00:00Z", Some("5")),
+            event("join_call", "2023-01-01T10:00:02Z", Some("5")),
+            event("call_ended", "2023-01-01T10:10:00Z", Some("5")),
+        ]);
+        assert_eq!(stats.session_count, 1);
+        assert_eq!(stats.longest_session.unwrap().kind, VoiceSessionKind::Call);
+    }
+
+    #[test]
+    fn drops_sessions_that_never_end() {
+        let stats = voice(vec![
+            event("join_voice_channel", "2023-01-01T00:00:00Z", Some("1")),
+            event("leave_voice_channel", "2023-01-01T13:00:00Z", Some("1")),
+            event("join_voice_channel", "2023-01-02T00:00:00Z", Some("1")),
+        ]);
+        assert_eq!(stats.session_count, 0);
+        assert_eq!(stats.unmatched_event_count, 2);
+    }
+
+    #[test]
+    fn spreads_minutes_over_hours() {
+        let mut hours = vec![0.0; 24];
+        let at = |s: &str| DateTime::parse_from_rfc3339(s).unwrap();
+        add_hour_minutes(
+            &mut hours,
+            at("2023-01-01T22:45:00Z"),
+            at("2023-01-02T00:30:00Z"),
+        );
+        assert_eq!(hours[22], 15.0);
+        assert_eq!(hours[23], 60.0);
+        assert_eq!(hours[0], 30.0);
+        assert_eq!(hours.iter().sum::<f64>(), 105.0);
+    }
+
+    #[test]
+    fn hours_use_the_offset_of_the_messages() {
+        let mut data = ExtractedData {
+            utc_offset_minutes: 120,
+            voice_events: vec![
+                event("join_voice_channel", "2023-01-01T10:00:00Z", Some("1")),
+                event("leave_voice_channel", "2023-01-01T10:30:00Z", Some("1")),
+            ],
+            ..ExtractedData::default()
+        };
+        Parser::new().compute_voice_stats(&mut data);
+        assert_eq!(data.voice.hours_minutes[12], 30.0);
+        assert_eq!(data.voice.hours_minutes[10], 0.0);
+    }
+}
@@ -0,0 +1,326 @@
+use chrono::{Datelike, Months, NaiveDate};
+use std::cmp::Reverse;
+use std::collections::{BTreeMap, HashMap, HashSet};
+
+use crate::models::{ExtractedData, MonthCount, Phrase, WordSpike, WordTrend, WordTrends};
+use crate::parser::{Parser, language};
+
+/// A month only counts as a spike when the word was used at least this many times in it...
+const SPIKE_MIN_COUNT: usize = 5;
+/// ...and this many times more often than in an average other month.
+const SPIKE_RATIO: f64 = 3.0;
+/// Phrase counts are pruned once either map holds this many phrases.
+const MAX_PHRASES: usize = 200_000;
+
+impl Parser {
+    /// Bigrams, trigrams and monthly word spikes. Expects `daily_values` and the languages to
+    /// be filled in; phrases made of nothing but their stopwords are left out.
+    pub(super) fn compute_word_trends(&self, extracted_data: &mut ExtractedData) {
+        let months = Parser::active_months(extracted_data);
+        let stopwords = language::detected_stopwords(extracted_data);
+        let mut bigrams: HashMap<String, usize> = HashMap::new();
+        let mut trigrams: HashMap<String, usize> = HashMap::new();
+        let mut monthly: HashMap<String, HashMap<NaiveDate, usize>> = HashMap::new();
+
+        for parsed in &extracted_data.channels {
+            for message in &parsed.messages {
+                let tokens = Parser::tokens(&message.contents);
+                count_phrases(&mut bigrams, &tokens, 2, &stopwords);
+                count_phrases(&mut trigrams, &tokens, 3, &stopwords);
+                let Some(month) = message.local_date().map(month_start) else {
+                    continue;
+                };
+                for token in tokens {
+                    if token.chars().count() >= self.options.min_word_length {
+                        *monthly.entry(token).or_default().entry(month).or_insert(0) += 1;
+                    }
+                }
+            }
+        }
+
+        let mut spikes: Vec<WordSpike> = Vec::new();
+        if months.len() >= 3 {
+            let other_months = (months.len() - 1) as f64;
+            for (word, counts) in monthly {
+                let total: usize = counts.values().sum();
+                let best = counts
+                    .iter()
+                    .filter(|(_, count)| **count >= SPIKE_MIN_COUNT)
+                    .map(|(month, count)| (*month, *count, (total - count) as f64 / other_months))
+                    .filter(|(_, count, baseline)| *count as f64 >= SPIKE_RATIO * baseline.max(1.0))
+                    .max_by_key(|(month, count, _)| (*count, Reverse(*month)));
+                if let Some((month, count, baseline)) = best {
+                    spikes.push(WordSpike {
+                        word,
+                        month,
+                        count,
+                        baseline,
+                    });
+                }
+            }
+        }
+        spikes.sort_by(|a, b| {
+            spike_score(b)
+                .total_cmp(&spike_score(a))
+                .then_with(|| a.word.cmp(&b.word))
+        });
+        spikes.truncate(self.options.top_count);
+
+        extracted_data.word_trends = WordTrends {
+            bigrams: self.top_phrases(bigrams),
+            trigrams: self.top_phrases(trigrams),
+            spikes,
+        };
+    }
+
+    /// Counts how often `query` (a word or phrase) was written in each month.
+    pub fn word_trend(data: &ExtractedData, query: &str) -> WordTrend {
+        let needle = Parser::tokens(query);
+        let mut counts: BTreeMap<NaiveDate, usize> = Parser::active_months(data)
+            .into_iter()
+            .map(|month| (month, 0))
+            .collect();
+
+        if !needle.is_empty() {
+            for parsed in &data.channels {
+                for message in &parsed.messages {
+                    let Some(date) = message.local_date() else {
+                        continue;
+                    };
+                    let matches = Parser::tokens(&message.contents)
+                        .windows(needle.len())
+                        .filter(|window| *window == needle.as_slice())
+                        .count();
+                    if matches > 0 {
+                        *counts.entry(month_start(date)).or_insert(0) += matches;
+                    }
+                }
+            }
+        }
+
+        WordTrend {
+            query: needle.join(" "),
+            total: counts.values().sum(),
+            months: counts
+                .into_iter()
+                .map(|(month, count)| MonthCount { month, count })
+                .collect(),
+        }
+    }
+
+    /// Lowercased words with surrounding punctuation removed. Links are left out.
+    pub(super) fn tokens(contents: &str) -> Vec<String> {
+        contents
+            .split_whitespace()
+            .filter(|word| !word.contains("://"))
+            .map(|word| {
+                word.trim_matches(|c: char| !c.is_alphanumeric())
+                    .to_lowercase()
+            })
+            .filter(|word| !word.is_empty())
+            .collect()
+    }
+
+    fn active_months(data: &ExtractedData) -> Vec<NaiveDate> {
+        let (Some(first), Some(last)) = (
+            data.daily_values.keys().next(),
+            data.daily_values.keys().next_back(),
+        ) else {
+            return Vec::new();
+        };
+        let last = month_start(*last);
+        let mut months = vec![month_start(*first)];
+        while let Some(next) = months
+            .last()
+            .and_then(|m| m.checked_add_months(Months::new(1)))
+            && next <= last
+        {
+            months.push(next);
+        }
+        months
+    }
+
+    fn top_phrases(&self, counts: HashMap<String, usize>) -> Vec<Phrase> {
+        // A phrase written once isn't a habit.
+        let mut phrases: Vec<Phrase> = counts
+            .into_iter()
+            .filter(|(_, count)| *count > 1)
+            .map(|(phrase, count)| Phrase { phrase, count })
+            .collect();
+        phrases.sort_by(|a, b| b.count.cmp(&a.count).then_with(|| a.phrase.cmp(&b.phrase)));
+        phrases.truncate(self.options.top_count);
+        phrases
+    }
+}
+
+fn count_phrases(
+    counts: &mut HashMap<String, usize>,
+    tokens: &[String],
+    length: usize,
+    stopwords: &HashSet<&str>,
+) {
+    for phrase in tokens.windows(length) {
+        if phrase.iter().all(|word| stopwords.contains(word.as_str())) {
+            continue;
+        }
+        *counts.entry(phrase.join(" ")).or_insert(0) += 1;
+    }
+    if counts.len() > MAX_PHRASES {
+        prune_phrases(counts, MAX_PHRASES / 2);
+    }
+}
+
+/// Drops the rarest phrases until at most `keep` are left. Phrases dropped early lose their
+/// count, which only matters for phrases far from the top.
+fn prune_phrases(counts: &mut HashMap<String, usize>, keep: usize) {
+    let mut floor = 1;
+    while counts.len() > keep {
+        counts.retain(|_, count| *count > floor);
+        floor += 1;
+    }
+}
+
+fn month_start(date: NaiveDate) -> NaiveDate {
+    date.with_day(1).unwrap_or(date)
+}
+
+fn spike_score(spike: &WordSpike) -> f64 {
+    spike.count as f64 / spike.baseline.max(1.0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::fixtures::{data, guild_channel};
+
+    fn day(s: &str) -> NaiveDate {
+        s.parse().unwrap()
+    }
+
+    fn trends() -> ExtractedData {
+        let mut timestamps = vec!["2023-01-03T10:00:00Z".to_string()];
+        timestamps.extend((1..=6).map(|d| format!("2023-02-{:02}T10:00:00Z", d)));
+        timestamps.extend([
+            "2023-03-10T10:00:00Z".to_string(),
+            "2023-03-11T10:00:00Z".to_string(),
+        ]);
+        let contents = |index: usize| match index {
+            0 => "hello pineapple",
+            1..=6 => "Pineapple again!",
+            _ => "hello world",
+        };
+        let messages: Vec<(&str, &str)> = timestamps
+            .iter()
+            .enumerate()
+            .map(|(index, t)| (t.as_str(), contents(index)))
+            .collect();
+        let mut data = data(vec![guild_channel("1", "general", &messages)]);
+        Parser::new().compute_channel_stats(&mut data);
+        data
+    }
+
+    #[test]
+    fn tokens_drop_punctuation_and_links() {
+        assert_eq!(
+            Parser::tokens("Hello, World! see https://example.com (now)"),
+            ["hello", "world", "see", "now"]
+        );
+        assert!(Parser::tokens("?! ...").is_empty());
+    }
+
+    #[test]
+    fn finds_monthly_spikes() {
+        let data = trends();
+        let spikes = &data.word_trends.spikes;
+        assert_eq!(spikes.len(), 1);
+        assert_eq!(spikes[0].word, "pineapple");
+        assert_eq!(spikes[0].month, day("2023-02-01"));
+        assert_eq!(spikes[0].count, 6);
+        assert_eq!(spikes[0].baseline, 0.5);
+
+        let bigrams = &data.word_trends.bigrams;
+        assert_eq!(bigrams[0].phrase, "pineapple again");
+        assert_eq!(bigrams[0].count, 6);
+        // "hello world" is made of stopwords only.
+        assert_eq!(bigrams.len(), 1);
+    }
+
+    #[test]
+    fn spikes_need_the_minimum_count() {
+        let spikes = |count: usize| {
+            let mut timestamps = vec!["2023-01-03T10:00:00Z".to_string()];
+            timestamps.extend((1..=count).map(|d| format!("2023-02-{:02}T10:00:00Z", d)));
+            timestamps.push("2023-03-10T10:00:00Z".to_string());
+            let messages: Vec<(&str, &str)> = timestamps
+                .iter()
+                .map(|t| (t.as_str(), "pineapple"))
+                .collect();
+            let mut data = data(vec![guild_channel("1", "general", &messages)]);
+            Parser::new().compute_channel_stats(&mut data);
+            data.word_trends.spikes.len()
+        };
+        assert_eq!(spikes(SPIKE_MIN_COUNT - 1), 0);
+        assert_eq!(spikes(SPIKE_MIN_COUNT), 1);
+    }
+
+    #[test]
+    fn spikes_need_three_active_months() {
+        let timestamps: Vec<String> = (1..=6)
+            .map(|d| format!("2023-02-{:02}T10:00:00Z", d))
+            .collect();
+        let mut messages: Vec<(&str, &str)> = timestamps
+            .iter()
+            .map(|t| (t.as_str(), "pineapple"))
+            .collect();
+        messages.push(("2023-01-03T10:00:00Z", "pineapple"));
+        let mut data = data(vec![guild_channel("1", "general", &messages)]);
+        Parser::new().compute_channel_stats(&mut data);
+        assert!(data.word_trends.spikes.is_empty());
+    }
+
+    #[test]
+    fn phrases_skip_stopword_only_runs() {
+        let messages = [
+            ("2023-01-01T10:00:00Z", "one of the pineapples"),
+            ("2023-01-02T10:00:00Z", "one of the pineapples"),
+        ];
+        let mut data = data(vec![guild_channel("1", "general", &messages)]);
+        Parser::new().compute_channel_stats(&mut data);
+        let bigrams: Vec<&str> = data
+            .word_trends
+            .bigrams
+            .iter()
+            .map(|p| p.phrase.as_str())
+            .collect();
+        assert!(!bigrams.contains(&"of the"));
+        assert!(bigrams.contains(&"the pineapples"));
+    }
+
+    #[test]
+    fn pruning_keeps_the_most_common_phrases() {
+        let mut counts: HashMap<String, usize> = (0..10)
+            .map(|count| (format!("phrase {}", count), count))
+            .collect();
+        prune_phrases(&mut counts, 3);
+        let mut kept: Vec<usize> = counts.into_values().collect();
+        kept.sort();
+        assert_eq!(kept, [7, 8, 9]);
+    }
+
+    #[test]
+    fn word_trend_lists_every_active_month() {
+        let trend = Parser::word_trend(&trends(), "Hello World");
+        assert_eq!(trend.query, "hello world");
+        assert_eq!(trend.total, 2);
+        let counts: Vec<(NaiveDate, usize)> =
+            trend.months.iter().map(|m| (m.month, m.count)).collect();
+        assert_eq!(
+            counts,
+            [
+                (day("2023-01-01"), 0),
+                (day("2023-02-01"), 0),
+                (day("2023-03-01"), 2)
+            ]
+        );
+    }
+}
@@ -0,0 +1,149 @@
+use chrono::{DateTime, Datelike, FixedOffset};
+use std::collections::{BTreeSet, HashMap};
+
+use crate::models::{ExtractedData, NewGuild, ParsedChannel, YearSummary};
+use crate::parser::Parser;
+
+impl Parser {
+    /// Builds a [`YearSummary`] for every year with messages by running the regular message
+    /// statistics over that year's messages only.
+    pub(super) fn compute_years(&self, extracted_data: &mut ExtractedData) {
+        let years: BTreeSet<i32> = extracted_data
+            .daily_values
+            .keys()
+            .map(|date| date.year())
+            .collect();
+        let new_guilds = Parser::first_guild_messages(&extracted_data.channels);
+
+        let mut summaries = Vec::with_capacity(years.len());
+        for year in years {
+            let channels: Vec<ParsedChannel> = extracted_data
+                .channels
+                .iter()
+                .filter_map(|parsed| {
+                    let messages: Vec<_> = parsed
+                        .messages
+                        .iter()
+                        .filter(|m| m.local_date().is_some_and(|d| d.year() == year))
+                        .cloned()
+                        .collect();
+                    (!messages.is_empty()).then(|| ParsedChannel {
+                        channel: parsed.channel.clone(),
+                        name: parsed.name.clone(),
+                        messages,
+                        attachment_only: Vec::new(),
+                    })
+                })
+                .collect();
+
+            let mut scoped = ExtractedData {
+                user: extracted_data.user.clone(),
+                channels,
+                ..Default::default()
+            };
+            self.compute_message_stats(&mut scoped);
+
+            summaries.push(YearSummary {
+                year,
+                message_count: scoped.message_count,
+                character_count: scoped.character_count,
+                active_days: scoped.daily_values.len(),
+                top_channels: scoped.top_channels,
+                top_dms: scoped.top_dms,
+                top_group_dms: scoped.top_group_dms,
+                favorite_words: scoped.favorite_words,
+                hours_values: scoped.hours_values,
+                new_guilds: new_guilds
+                    .iter()
+                    .filter(|g| g.first_message_at.year() == year)
+                    .cloned()
+                    .collect(),
+                spent_by_currency: extracted_data
+                    .payments
+                    .by_year
+                    .get(&year)
+                    .cloned()
+                    .unwrap_or_default(),
+                spent: extracted_data.payments.spent_by_year.get(&year).copied(),
+            });
+        }
+
+        extracted_data.years = summaries;
+    }
+
+    /// The package has no join dates, so a guild counts as joined when the account first
+    /// wrote in any of its channels.
+    fn first_guild_messages(channels: &[ParsedChannel]) -> Vec<NewGuild> {
+        let mut first: HashMap<&str, (&str, DateTime<FixedOffset>)> = HashMap::new();
+        for parsed in channels {
+            let Some(guild) = &parsed.channel.guild else {
+                continue;
+            };
+            let Some(sent_at) = parsed.messages.iter().filter_map(|m| m.local_time()).min() else {
+                continue;
+            };
+            // Guilds from old packages may lack an id.
+            let key = if guild.id.is_empty() {
+                &guild.name
+            } else {
+                &guild.id
+            };
+            let entry = first.entry(key.as_str()).or_insert((&guild.name, sent_at));
+            if sent_at < entry.1 {
+                entry.1 = sent_at;
+            }
+        }
+
+        let mut guilds: Vec<NewGuild> = first
+            .into_iter()
+            .map(|(id, (name, first_message_at))| NewGuild {
+                id: id.to_string(),
+                name: name.to_string(),
+                first_message_at,
+            })
+            .collect();
+        guilds.sort_by_key(|g| g.first_message_at);
+        guilds
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::fixtures::{data, guild_channel};
+
+    #[test]
+    fn splits_stats_by_year() {
+        let mut data = data(vec![
+            guild_channel(
+                "1",
+                "general",
+                &[
+                    ("2022-12-31T23:00:00Z", "last of the year"),
+                    ("2023-01-01T10:00:00Z", "happy new year"),
+                    ("2023-01-02T10:00:00Z", "back to work"),
+                ],
+            ),
+            guild_channel("2", "later", &[("2023-06-01T10:00:00Z", "hello")]),
+        ]);
+        data.channels[1].channel.guild.as_mut().unwrap().id = "700000000000000002".to_string();
+        Parser::new().compute_channel_stats(&mut data);
+
+        let years: Vec<(i32, usize, usize)> = data
+            .years
+            .iter()
+            .map(|y| (y.year, y.message_count, y.active_days))
+            .collect();
+        assert_eq!(years, [(2022, 1, 1), (2023, 3, 3)]);
+        let new_guilds = |index: usize| -> Vec<&str> {
+            data.years[index]
+                .new_guilds
+                .iter()
+                .map(|g| g.id.as_str())
+                .collect()
+        };
+        assert_eq!(new_guilds(0), ["700000000000000001"]);
+        assert_eq!(new_guilds(1), ["700000000000000002"]);
+        assert_eq!(data.years[0].hours_values[23], 1);
+    }
+}
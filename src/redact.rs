@@ -0,0 +1,506 @@
+//! Pseudonymization for sharing stats without exposing who or where.
+//!
+//! Redaction runs on [`ExtractedData`] before it reaches any output, so the JSON API, CSV and
+//! SQLite exports and HTML reports all see the same pseudonyms. A pseudonym is a salted hash
+//! of the original value: the same DM partner or server gets the same name everywhere, and
+//! ids keep joining up in the SQLite export. The salt is a random secret that never appears in
+//! any output, so pseudonyms can't be reversed by hashing guessed ids or names.
+
+use anyhow::Result;
+use sha2::{Digest, Sha256};
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+use std::sync::LazyLock;
+
+use crate::cache::{CachedPackage, PackageCache};
+use crate::models::{
+    AnalyticsEvent, ExtractedData, FavoriteWord, MergeReport, TopChannel, TopDM, TopGroupDM,
+    UserData,
+};
+
+#[derive(Debug, Clone, Default)]
+#[non_exhaustive]
+pub struct RedactionOptions {
+    /// Secret mixed into every pseudonym. Defaults to a random salt for this process, so
+    /// pseudonyms only stay stable between runs when a stored salt is passed, see
+    /// [`RedactionOptions::load_salt`].
+    pub salt: Option<String>,
+    /// Favorite words to hide, compared case-insensitively.
+    pub blocklist: Vec<String>,
+}
+
+impl RedactionOptions {
+    pub fn salt(mut self, salt: impl Into<String>) -> Self {
+        self.salt = Some(salt.into());
+        self
+    }
+
+    pub fn blocklist(mut self, blocklist: Vec<String>) -> Self {
+        self.blocklist = blocklist;
+        self
+    }
+
+    /// Reads a blocklist file with one word per line. Blank lines and `#` comments are ignored.
+    pub fn load_blocklist(path: &Path) -> Result<Vec<String>> {
+        Ok(std::fs::read_to_string(path)?
+            .lines()
+            .map(str::trim)
+            .filter(|line| !line.is_empty() && !line.starts_with('#'))
+            .map(str::to_string)
+            .collect())
+    }
+
+    /// Reads the salt stored at `path`, creating it with a new random salt on first use.
+    pub fn load_salt(path: &Path) -> Result<String> {
+        if let Ok(salt) = std::fs::read_to_string(path)
+            && !salt.trim().is_empty()
+        {
+            return Ok(salt.trim().to_string());
+        }
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        let salt = random_salt()?;
+        std::fs::write(path, &salt)?;
+        eprintln!("[debug] Created redaction salt at {}", path.display());
+        Ok(salt)
+    }
+
+    /// `redaction-salt` next to [`PackageCache::default_path`].
+    pub fn default_salt_path() -> PathBuf {
+        PackageCache::default_path().with_file_name("redaction-salt")
+    }
+}
+
+fn random_salt() -> Result<String> {
+    let mut bytes = [0u8; 32];
+    getrandom::fill(&mut bytes).map_err(|e| anyhow::anyhow!("No random source for salt: {}", e))?;
+    Ok(bytes.iter().map(|b| format!("{:02x}", b)).collect())
+}
+
+static PROCESS_SALT: LazyLock<String> =
+    LazyLock::new(|| random_salt().expect("random source for redaction salt"));
+
+struct Redactor {
+    salt: String,
+    blocklist: HashSet<String>,
+}
+
+impl Redactor {
+    fn new(options: &RedactionOptions) -> Self {
+        Self {
+            salt: options.salt.clone().unwrap_or_else(|| PROCESS_SALT.clone()),
+            blocklist: options.blocklist.iter().map(|w| w.to_lowercase()).collect(),
+        }
+    }
+
+    fn hash(&self, kind: &str, value: &str) -> String {
+        let mut hasher = Sha256::new();
+        hasher.update(self.salt.as_bytes());
+        hasher.update(b":");
+        hasher.update(kind.as_bytes());
+        hasher.update(b":");
+        hasher.update(value.as_bytes());
+        format!("{:x}", hasher.finalize())
+    }
+
+    fn name(&self, kind: &str, value: &str) -> String {
+        format!("{} {}", kind, &self.hash(kind, value)[..6])
+    }
+
+    fn id(&self, value: &str) -> String {
+        self.hash("id", value)[..16].to_string()
+    }
+
+    /// Pseudonymizes another user's profile, keyed by their user id so they get the same
+    /// name in DMs, group DMs and relationships.
+    fn user(&self, user: &mut UserData, user_id: &str) {
+        user.id = self.id(user_id);
+        user.username = self.name("User", user_id);
+        user.global_name = None;
+        user.avatar = None;
+    }
+
+    fn is_blocked(&self, word: &str) -> bool {
+        self.blocklist.contains(&word.to_lowercase())
+    }
+
+    fn top_dms(&self, dms: &mut [TopDM]) {
+        for dm in dms {
+            dm.id = self.id(&dm.id);
+            if let Some(user_data) = dm.user_data.as_mut() {
+                self.user(user_data, &dm.dm_user_id);
+            }
+            dm.dm_user_id = self.id(&dm.dm_user_id);
+        }
+    }
+
+    fn top_group_dms(&self, groups: &mut [TopGroupDM]) {
+        for group in groups {
+            group.name = self.name("Group", &group.id);
+            group.id = self.id(&group.id);
+            for participant in &mut group.participants {
+                let id = participant.id.clone();
+                self.user(participant, &id);
+            }
+        }
+    }
+
+    fn top_channels(&self, channels: &mut [TopChannel]) {
+        for channel in channels {
+            channel.name = self.name("Channel", &channel.name);
+            if let Some(guild_name) = channel.guild_name.as_mut() {
+                *guild_name = self.name("Server", guild_name);
+            }
+        }
+    }
+
+    fn phrase(&self, phrase: &mut String) {
+        if phrase.split(' ').any(|word| self.is_blocked(word)) {
+            *phrase = self.name("phrase", phrase);
+        }
+    }
+
+    fn favorite_words(&self, words: &mut [FavoriteWord]) {
+        for word in words {
+            if self.is_blocked(&word.word) {
+                word.word = self.name("word", &word.word.to_lowercase());
+            }
+        }
+    }
+}
+
+/// Returns a copy of `data` with DM partners, friends, channel and guild names, message
+/// contents, blocklisted words and payment descriptions replaced by pseudonyms.
+///
+/// The account's own name and avatar are kept, since it is the one sharing the stats, but its
+/// id is pseudonymized, its email, phone number and notes are removed and gift codes are
+/// replaced by a hash.
+pub fn redact(data: &ExtractedData, options: &RedactionOptions) -> ExtractedData {
+    let redactor = Redactor::new(options);
+    let mut redacted = data.clone();
+    redacted.redacted = true;
+
+    redactor.top_dms(&mut redacted.top_dms);
+    redactor.top_group_dms(&mut redacted.top_group_dms);
+    redactor.top_channels(&mut redacted.top_channels);
+
+    redacted.guilds = data
+        .guilds
+        .iter()
+        .map(|(id, name)| (redactor.id(id), redactor.name("Server", name)))
+        .collect();
+
+    for parsed in &mut redacted.channels {
+        parsed.name = redactor.name("Channel", &parsed.name);
+        parsed.channel.id = redactor.id(&parsed.channel.id);
+        if let Some(recipients) = parsed.channel.recipients.as_mut() {
+            *recipients = recipients.iter().map(|id| redactor.id(id)).collect();
+        }
+        if let Some(guild) = parsed.channel.guild.as_mut() {
+            guild.id = redactor.id(&guild.id);
+            guild.name = redactor.name("Server", &guild.name);
+        }
+        for message in parsed.messages.iter_mut().chain(&mut parsed.attachment_only) {
+            message.contents.clear();
+            message.words.clear();
+            message.attachments.clear();
+        }
+    }
+
+    let activity = &mut redacted.activity;
+    for highlight in [&mut activity.first_message, &mut activity.last_message]
+        .into_iter()
+        .flatten()
+    {
+        highlight.channel_id = redactor.id(&highlight.channel_id);
+        highlight.channel_name = redactor.name("Channel", &highlight.channel_name);
+        highlight.contents.clear();
+    }
+    if let Some(day) = activity.busiest_day.as_mut() {
+        day.top_channel_id = redactor.id(&day.top_channel_id);
+        day.top_channel = redactor.name("Channel", &day.top_channel);
+    }
+    for channel in &mut activity.channel_sessions {
+        channel.channel_id = redactor.id(&channel.channel_id);
+        channel.name = redactor.name("Channel", &channel.name);
+    }
+
+    redactor.favorite_words(&mut redacted.favorite_words);
+    let trends = &mut redacted.word_trends;
+    for phrase in trends.bigrams.iter_mut().chain(trends.trigrams.iter_mut()) {
+        redactor.phrase(&mut phrase.phrase);
+    }
+    for spike in &mut trends.spikes {
+        redactor.phrase(&mut spike.word);
+    }
+    for language in &mut redacted.languages.languages {
+        redactor.favorite_words(&mut language.favorite_words);
+    }
+    for channel in &mut redacted.languages.channels {
+        channel.channel_id = redactor.id(&channel.channel_id);
+        channel.name = redactor.name("Channel", &channel.name);
+    }
+    let sentiment = &mut redacted.sentiment;
+    for channel in sentiment.channels.iter_mut().chain(sentiment.dms.iter_mut()) {
+        channel.channel_id = redactor.id(&channel.channel_id);
+        channel.name = redactor.name("Channel", &channel.name);
+    }
+    let lengths = &mut redacted.lengths;
+    for channel in lengths.channels.iter_mut().chain(lengths.dms.iter_mut()) {
+        channel.channel_id = redactor.id(&channel.channel_id);
+        channel.name = redactor.name("Channel", &channel.name);
+    }
+    let voice = &mut redacted.voice;
+    for (kind, times) in [("Server", &mut voice.guilds), ("Channel", &mut voice.channels)] {
+        for time in times.iter_mut() {
+            time.name = time.name.as_ref().map(|name| redactor.name(kind, name));
+            time.id = redactor.id(&time.id);
+        }
+    }
+    if let Some(session) = voice.longest_session.as_mut() {
+        session.channel_id = session.channel_id.as_ref().map(|id| redactor.id(id));
+        session.channel_name = session
+            .channel_name
+            .as_ref()
+            .map(|name| redactor.name("Channel", name));
+        session.guild_id = session.guild_id.as_ref().map(|id| redactor.id(id));
+        session.guild_name = session
+            .guild_name
+            .as_ref()
+            .map(|name| redactor.name("Server", name));
+    }
+
+    for summary in &mut redacted.years {
+        redactor.top_dms(&mut summary.top_dms);
+        redactor.top_group_dms(&mut summary.top_group_dms);
+        redactor.top_channels(&mut summary.top_channels);
+        redactor.favorite_words(&mut summary.favorite_words);
+        for guild in &mut summary.new_guilds {
+            guild.id = redactor.id(&guild.id);
+            guild.name = redactor.name("Server", &guild.name);
+        }
+    }
+
+    if let Some(user) = redacted.user.as_mut() {
+        user.id = redactor.id(&user.id);
+        for payment in &mut user.payments {
+            payment.description = redactor.name("Purchase", &payment.description);
+        }
+
+        user.email = None;
+        user.phone = None;
+        user.notes.clear();
+        for connection in &mut user.connections {
+            connection.name = redactor.name("Account", &connection.id);
+            connection.id = redactor.id(&connection.id);
+        }
+        for relationship in &mut user.relationships {
+            relationship.nickname = None;
+            if let Some(other) = relationship.user.as_mut() {
+                redactor.user(other, &relationship.id);
+            }
+            relationship.id = redactor.id(&relationship.id);
+        }
+        for settings in &mut user.guild_settings {
+            settings.guild_id = settings.guild_id.as_ref().map(|id| redactor.id(id));
+        }
+        // Unused gift codes can still be redeemed by whoever reads them.
+        for gift in &mut user.gift_codes {
+            gift.code = redactor.hash("gift", &gift.code)[..16].to_string();
+        }
+    }
+    for period in &mut redacted.payments.subscriptions {
+        period.description = redactor.name("Purchase", &period.description);
+    }
+
+    for event in &mut redacted.voice_events {
+        event.channel_id = event.channel_id.as_ref().map(|id| redactor.id(id));
+        event.guild_id = event.guild_id.as_ref().map(|id| redactor.id(id));
+    }
+
+    redacted
+}
+
+/// Pseudonymizes the ids of analytics events read straight from a package, matching the ids
+/// [`redact`] gives the package's data with the same options.
+pub fn event_redactor(options: &RedactionOptions) -> impl Fn(&mut AnalyticsEvent) + use<> {
+    let redactor = Redactor::new(options);
+    move |event| {
+        event.channel_id = event.channel_id.as_ref().map(|id| redactor.id(id));
+        event.guild_id = event.guild_id.as_ref().map(|id| redactor.id(id));
+    }
+}
+
+/// Redacts an entry of the cache's package list. The list spans every cached account, so
+/// unlike [`redact`], which keeps the sharing account's own name, the account and the file
+/// name are pseudonymized too.
+pub fn redact_cached_package(package: &CachedPackage, options: &RedactionOptions) -> CachedPackage {
+    let redactor = Redactor::new(options);
+    let mut redacted = package.clone();
+    redacted.source = redactor.name("Package", &package.source);
+    redacted.username = package
+        .user_id
+        .as_deref()
+        .map(|id| redactor.name("User", id));
+    redacted.user_id = package.user_id.as_deref().map(|id| redactor.id(id));
+    redacted
+}
+
+/// Redacts the report of a merge: the account and the file names are pseudonymized, the
+/// counts are kept.
+pub fn redact_merge_report(report: &MergeReport, options: &RedactionOptions) -> MergeReport {
+    let redactor = Redactor::new(options);
+    let mut redacted = report.clone();
+    redacted.user_id = redactor.id(&report.user_id);
+    for package in &mut redacted.packages {
+        package.source = redactor.name("Package", &package.source);
+    }
+    redacted
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::fixtures::{USER_ID, compute_stats, data, dm_channel, temp_path, user};
+    use serde_json::json;
+
+    const FRIEND_ID: &str = "222222222222222222";
+
+    fn package() -> ExtractedData {
+        let mut data = data(vec![dm_channel(
+            "800000000000000002",
+            FRIEND_ID,
+            &[
+                ("2023-01-01T10:00:00Z", "secret meeting tonight"),
+                ("2023-01-01T10:01:00Z", "secret meeting again"),
+            ],
+        )]);
+        data.user = Some(user(json!({
+            "email": "t@example.com",
+            "notes": {FRIEND_ID: "good friend"},
+            "relationships": [{"id": FRIEND_ID, "type": 1, "nickname": "bestie",
+                "user": {"id": FRIEND_ID, "username": "friend", "avatar": null}}],
+            "gift_codes": [{"code": "ABCD-EFGH-IJKL"}],
+        })));
+        compute_stats(&mut data);
+        data
+    }
+
+    #[test]
+    fn pseudonyms_join_up_across_the_data() {
+        let data = package();
+        let options = RedactionOptions::default().blocklist(vec!["SECRET".into()]);
+        let redacted = redact(&data, &options);
+
+        let channel = &redacted.channels[0];
+        let recipients = channel.channel.recipients.as_ref().unwrap();
+        assert_eq!(recipients[0], redacted.user.as_ref().unwrap().id);
+        assert_ne!(recipients[0], USER_ID);
+        assert_ne!(recipients[1], FRIEND_ID);
+        assert!(redacted.redacted);
+        assert!(channel.name.starts_with("Channel "));
+        assert!(channel.messages.iter().all(|m| m.contents.is_empty() && m.words.is_empty()));
+
+        let user = redacted.user.as_ref().unwrap();
+        assert_eq!(user.relationships[0].id, recipients[1]);
+        assert_eq!(redacted.top_dms[0].dm_user_id, recipients[1]);
+        assert_eq!(user.relationships[0].nickname, None);
+        assert_eq!(user.email, None);
+        assert!(user.notes.is_empty());
+        assert_eq!(user.username, "tester");
+
+        let gift = &user.gift_codes[0].code;
+        assert_eq!(gift.len(), 16);
+        assert_ne!(gift, "ABCD-EFGH-IJKL");
+        assert!(redacted.favorite_words.iter().all(|w| w.word != "secret"));
+        assert!(redacted.favorite_words.iter().any(|w| w.word == "meeting"));
+    }
+
+    #[test]
+    fn event_ids_match_the_redacted_channels() {
+        let data = package();
+        let options = RedactionOptions::default();
+        let redacted = redact(&data, &options);
+        let mut event = AnalyticsEvent {
+            event_type: "join_call".to_string(),
+            timestamp: "2023-01-01T10:00:00Z".to_string(),
+            channel_id: Some("800000000000000002".to_string()),
+            guild_id: None,
+        };
+        event_redactor(&options)(&mut event);
+        assert_eq!(event.channel_id.as_ref(), Some(&redacted.channels[0].channel.id));
+    }
+
+    #[test]
+    fn pseudonyms_cannot_be_recomputed_from_the_output() {
+        let data = package();
+        let salt_path = temp_path("redaction-salt");
+        let salt = RedactionOptions::load_salt(&salt_path).unwrap();
+        assert_eq!(RedactionOptions::load_salt(&salt_path).unwrap(), salt);
+        std::fs::remove_file(&salt_path).unwrap();
+
+        let redacted = redact(&data, &RedactionOptions::default().salt(salt.clone()));
+        let json = serde_json::to_string(&redacted).unwrap();
+        let html_path = temp_path("redacted.html");
+        crate::export::export_html(&redacted, &html_path).unwrap();
+        let html = std::fs::read_to_string(&html_path).unwrap();
+        std::fs::remove_file(&html_path).unwrap();
+        for output in [&json, &html] {
+            assert!(!output.contains(USER_ID));
+            assert!(!output.contains(&salt));
+        }
+
+        // Try every string and number in the output as the salt.
+        fn candidates(value: &serde_json::Value, found: &mut Vec<String>) {
+            match value {
+                serde_json::Value::String(s) => found.push(s.clone()),
+                serde_json::Value::Number(n) => found.push(n.to_string()),
+                serde_json::Value::Array(values) => {
+                    values.iter().for_each(|v| candidates(v, found))
+                }
+                serde_json::Value::Object(map) => map.iter().for_each(|(k, v)| {
+                    found.push(k.clone());
+                    candidates(v, found);
+                }),
+                _ => {}
+            }
+        }
+        let mut found = vec![String::new(), USER_ID.to_string()];
+        candidates(&serde_json::from_str(&json).unwrap(), &mut found);
+        let friend = &redacted.channels[0].channel.recipients.as_ref().unwrap()[1];
+        for guess in found {
+            let guessed = Redactor::new(&RedactionOptions::default().salt(guess));
+            assert_ne!(&guessed.id(FRIEND_ID), friend);
+        }
+    }
+
+    #[test]
+    fn salt_changes_every_pseudonym() {
+        let data = package();
+        let first = redact(&data, &RedactionOptions::default());
+        let second = redact(&data, &RedactionOptions::default().salt("other"));
+        let again = redact(&data, &RedactionOptions::default());
+        assert_eq!(first.channels[0].name, again.channels[0].name);
+        assert_ne!(first.channels[0].name, second.channels[0].name);
+        assert_ne!(first.channels[0].channel.id, second.channels[0].channel.id);
+    }
+
+    #[test]
+    fn cached_packages_hide_the_account() {
+        let package = CachedPackage {
+            hash: "abc".to_string(),
+            source: "/home/someone/package.zip".to_string(),
+            user_id: Some(USER_ID.to_string()),
+            username: Some("tester".to_string()),
+            parsed_at: "2023-01-01T00:00:00Z".to_string(),
+            channel_count: 1,
+            message_count: 2,
+        };
+        let redacted = redact_cached_package(&package, &RedactionOptions::default());
+        assert!(redacted.source.starts_with("Package "));
+        assert!(redacted.username.as_deref().is_some_and(|name| name.starts_with("User ")));
+        assert_ne!(redacted.user_id.as_deref(), Some(USER_ID));
+        assert_eq!((redacted.hash.as_str(), redacted.message_count), ("abc", 2));
+    }
+}
@@ -0,0 +1,284 @@
+//! Offline, lexicon-based sentiment scoring of message contents.
+//!
+//! Every word found in the lexicon adds its valence to the message, and a message scores the
+//! average valence of its words scaled into -1..1. There is no model and no randomness, so the
+//! same package and lexicon always give the same numbers. A small English lexicon with common
+//! chat slang is bundled; teams can load their own from a CSV file in the same format as
+//! `assets/sentiment-lexicon.csv`.
+
+use anyhow::{Result, anyhow};
+use chrono::{Datelike, NaiveDate};
+use std::collections::{BTreeMap, HashMap};
+use std::path::Path;
+use std::sync::LazyLock;
+
+use crate::models::{ChannelSentiment, DateSentiment, ExtractedData, SentimentStats};
+
+const BUNDLED_LEXICON: &str = include_str!("../assets/sentiment-lexicon.csv");
+
+/// Valences in the lexicon range from `-MAX_VALENCE` to `MAX_VALENCE`.
+pub const MAX_VALENCE: f64 = 5.0;
+
+/// Words that flip the valence of the next two words.
+const NEGATIONS: &[&str] = &[
+    "not", "no", "never", "dont", "don't", "doesnt", "doesn't", "didnt", "didn't", "isnt", "isn't",
+    "wasnt", "wasn't", "cant", "can't", "wont", "won't", "aint", "ain't",
+];
+
+/// Channels need this many scored messages to be ranked...
+const MIN_CHANNEL_MESSAGES: usize = 5;
+/// ...and days this many to count as a most positive or negative day.
+const MIN_DAY_MESSAGES: usize = 3;
+const TOP_DAYS: usize = 5;
+
+/// Word valences, keyed by lowercase word or emoticon.
+#[derive(Debug, Clone, Default)]
+pub struct Lexicon {
+    scores: HashMap<String, f64>,
+}
+
+impl Lexicon {
+    /// The lexicon shipped with the crate, parsed on first use.
+    pub fn bundled() -> &'static Self {
+        static BUNDLED: LazyLock<Lexicon> =
+            LazyLock::new(|| Lexicon::from_csv(BUNDLED_LEXICON).expect("bundled lexicon is valid"));
+        &BUNDLED
+    }
+
+    /// Reads a lexicon from the CSV file at `path`, replacing the bundled one. Use
+    /// [`Lexicon::update`] to extend the bundled lexicon instead.
+    pub fn load(path: &Path) -> Result<Self> {
+        Self::from_csv(&std::fs::read_to_string(path)?)
+    }
+
+    /// Parses a table with `word` and `score` columns. Lines starting with `#` are comments.
+    pub fn from_csv(content: &str) -> Result<Self> {
+        let mut reader = csv::ReaderBuilder::new()
+            .comment(Some(b'#'))
+            .trim(csv::Trim::All)
+            .from_reader(content.as_bytes());
+        let mut lexicon = Self::default();
+        for record in reader.records() {
+            let record = record?;
+            let word = record.get(0).unwrap_or_default();
+            let value = record.get(1).unwrap_or_default();
+            let score: f64 = value
+                .parse()
+                .ok()
+                .filter(|score: &f64| score.abs() <= MAX_VALENCE)
+                .ok_or_else(|| anyhow!("Invalid score {:?} for {:?} in lexicon", value, word))?;
+            if !word.is_empty() {
+                lexicon.scores.insert(word.to_lowercase(), score);
+            }
+        }
+        Ok(lexicon)
+    }
+
+    /// Adds every word from `other`, replacing the scores of words both lexicons have.
+    pub fn update(&mut self, other: Lexicon) {
+        self.scores.extend(other.scores);
+    }
+
+    pub fn len(&self) -> usize {
+        self.scores.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.scores.is_empty()
+    }
+
+    /// Sentiment of `contents` from -1 to 1, or `None` when no word is in the lexicon.
+    pub fn score(&self, contents: &str) -> Option<f64> {
+        let mut total = 0.0;
+        let mut matched = 0;
+        let mut negated = 0;
+        for word in contents.split_whitespace() {
+            let word = word.to_lowercase();
+            let trimmed = word.trim_matches(|c: char| !c.is_alphanumeric() && c != '\'');
+            if NEGATIONS.contains(&trimmed) {
+                negated = 2;
+                continue;
+            }
+            // Emoticons are made of punctuation, so try the word as written first.
+            if let Some(score) = self.scores.get(&word).or_else(|| self.scores.get(trimmed)) {
+                total += if negated > 0 { -0.5 * score } else { *score };
+                matched += 1;
+            }
+            negated = (negated - 1).max(0);
+        }
+        (matched > 0).then(|| total / matched as f64 / MAX_VALENCE)
+    }
+}
+
+#[derive(Default)]
+struct Average {
+    total: f64,
+    count: usize,
+}
+
+impl Average {
+    fn add(&mut self, score: f64) {
+        self.total += score;
+        self.count += 1;
+    }
+
+    fn value(&self) -> f64 {
+        if self.count == 0 {
+            0.0
+        } else {
+            self.total / self.count as f64
+        }
+    }
+}
+
+pub(crate) fn sentiment(data: &ExtractedData, lexicon: &Lexicon) -> SentimentStats {
+    let mut overall = Average::default();
+    let mut channels: Vec<ChannelSentiment> = Vec::new();
+    let mut dms: Vec<ChannelSentiment> = Vec::new();
+    let mut months: BTreeMap<NaiveDate, Average> = BTreeMap::new();
+    let mut days: BTreeMap<NaiveDate, Average> = BTreeMap::new();
+
+    for parsed in &data.channels {
+        let mut channel = Average::default();
+        for message in &parsed.messages {
+            let Some(score) = lexicon.score(&message.contents) else {
+                continue;
+            };
+            overall.add(score);
+            channel.add(score);
+            if let Some(date) = message.local_date() {
+                days.entry(date).or_default().add(score);
+                months
+                    .entry(date.with_day(1).unwrap_or(date))
+                    .or_default()
+                    .add(score);
+            }
+        }
+
+        if channel.count < MIN_CHANNEL_MESSAGES {
+            continue;
+        }
+        let channel_type = parsed.channel.kind();
+        let entry = ChannelSentiment {
+            channel_id: parsed.channel.id.clone(),
+            name: parsed.name.clone(),
+            channel_type,
+            scored_message_count: channel.count,
+            average: channel.value(),
+        };
+        if channel_type.is_dm() {
+            dms.push(entry);
+        } else {
+            channels.push(entry);
+        }
+    }
+
+    for list in [&mut channels, &mut dms] {
+        list.sort_by(|a, b| {
+            b.scored_message_count
+                .cmp(&a.scored_message_count)
+                .then_with(|| a.name.cmp(&b.name))
+        });
+    }
+
+    let dated = |(date, average): (&NaiveDate, &Average)| DateSentiment {
+        date: *date,
+        scored_message_count: average.count,
+        average: average.value(),
+    };
+    let mut busy_days: Vec<DateSentiment> = days
+        .iter()
+        .filter(|(_, average)| average.count >= MIN_DAY_MESSAGES)
+        .map(dated)
+        .collect();
+    // Sorting is stable, so ties keep the earliest day first.
+    busy_days.sort_by(|a, b| b.average.total_cmp(&a.average));
+    let most_positive_days: Vec<DateSentiment> = busy_days
+        .iter()
+        .filter(|d| d.average > 0.0)
+        .take(TOP_DAYS)
+        .cloned()
+        .collect();
+    busy_days.sort_by(|a, b| a.average.total_cmp(&b.average));
+    let most_negative_days: Vec<DateSentiment> = busy_days
+        .into_iter()
+        .filter(|d| d.average < 0.0)
+        .take(TOP_DAYS)
+        .collect();
+
+    SentimentStats {
+        scored_message_count: overall.count,
+        average: overall.value(),
+        channels,
+        dms,
+        months: months.iter().map(dated).collect(),
+        most_positive_days,
+        most_negative_days,
+    }
+}
+
+/// Rescores the sentiment of `data` with another lexicon.
+pub fn score_sentiment(data: &mut ExtractedData, lexicon: &Lexicon) {
+    data.sentiment = sentiment(data, lexicon);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::fixtures::{data, guild_channel};
+
+    fn lexicon() -> Lexicon {
+        Lexicon::from_csv("word,score\ngreat,3\nawful,-4\n:(,-2\nlove,5\n").unwrap()
+    }
+
+    #[test]
+    fn scores_known_words_only() {
+        let lexicon = lexicon();
+        assert_eq!(lexicon.score("this is GREAT!"), Some(0.6));
+        assert_eq!(lexicon.score("great but awful"), Some(-0.1));
+        assert_eq!(lexicon.score("oh dear :("), Some(-0.4));
+        assert_eq!(lexicon.score("nothing to see"), None);
+    }
+
+    #[test]
+    fn negations_flip_the_next_two_words() {
+        let lexicon = lexicon();
+        assert_eq!(lexicon.score("not great"), Some(-0.3));
+        assert_eq!(lexicon.score("don't really love"), Some(-0.5));
+        assert_eq!(lexicon.score("not that much, great"), Some(0.6));
+    }
+
+    #[test]
+    fn rejects_out_of_range_scores() {
+        assert!(Lexicon::from_csv("word,score\nbest,6\n").is_err());
+        assert!(Lexicon::from_csv("word,score\nbest,x\n").is_err());
+        assert!(std::ptr::eq(Lexicon::bundled(), Lexicon::bundled()));
+        assert!(!Lexicon::bundled().is_empty());
+    }
+
+    #[test]
+    fn ranks_busy_channels_and_days() {
+        let messages: Vec<(&str, &str)> = vec![
+            ("2023-01-01T10:00:00Z", "great"),
+            ("2023-01-01T11:00:00Z", "love it"),
+            ("2023-01-01T12:00:00Z", "great"),
+            ("2023-01-02T10:00:00Z", "awful"),
+            ("2023-01-02T11:00:00Z", "awful"),
+            ("2023-01-02T12:00:00Z", "great"),
+            ("2023-01-03T12:00:00Z", "no words here"),
+        ];
+        let data = data(vec![
+            guild_channel("1", "general", &messages),
+            guild_channel("2", "quiet", &[("2023-01-01T10:00:00Z", "great")]),
+        ]);
+        let stats = sentiment(&data, &lexicon());
+
+        assert_eq!(stats.scored_message_count, 7);
+        assert_eq!(stats.channels.len(), 1);
+        assert_eq!(stats.channels[0].scored_message_count, 6);
+        assert_eq!(stats.months.len(), 1);
+        let day = |d: &DateSentiment| d.date.to_string();
+        assert_eq!(stats.most_positive_days.iter().map(day).collect::<Vec<_>>(), ["2023-01-01"]);
+        assert_eq!(stats.most_negative_days.iter().map(day).collect::<Vec<_>>(), ["2023-01-02"]);
+    }
+}
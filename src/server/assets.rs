@@ -0,0 +1,57 @@
+use rust_embed::RustEmbed;
+use warp::http::{HeaderValue, Response, StatusCode, header};
+use warp::hyper::body::Bytes;
+use warp::path::Tail;
+
+#[derive(RustEmbed)]
+#[folder = "$DPKV_UI_DIST/"]
+struct UiAssets;
+
+pub(super) fn serve(tail: Tail, if_none_match: Option<String>) -> Response<Bytes> {
+    let path = tail.as_str();
+    let (path, file) = match UiAssets::get(path) {
+        Some(file) => (path, file),
+        // Unknown routes without an extension belong to the SPA, so hand back index.html.
+        None if !path.contains('.') => match UiAssets::get("index.html") {
+            Some(file) => ("index.html", file),
+            None => return not_found(),
+        },
+        None => return not_found(),
+    };
+
+    let etag = format!("\"{}\"", hex_hash(&file.metadata.sha256_hash()));
+    let cache_control = if path.starts_with("assets/") {
+        // Vite fingerprints everything under assets/, so those never change in place.
+        "public, max-age=31536000, immutable"
+    } else {
+        "no-cache"
+    };
+
+    let mut response = if if_none_match.as_deref() == Some(etag.as_str()) {
+        let mut response = Response::new(Bytes::new());
+        *response.status_mut() = StatusCode::NOT_MODIFIED;
+        response
+    } else {
+        Response::new(Bytes::from(file.data.into_owned()))
+    };
+
+    let headers = response.headers_mut();
+    if let Ok(value) = HeaderValue::from_str(file.metadata.mimetype()) {
+        headers.insert(header::CONTENT_TYPE, value);
+    }
+    if let Ok(value) = HeaderValue::from_str(&etag) {
+        headers.insert(header::ETAG, value);
+    }
+    headers.insert(header::CACHE_CONTROL, HeaderValue::from_static(cache_control));
+    response
+}
+
+fn not_found() -> Response<Bytes> {
+    let mut response = Response::new(Bytes::from_static(b"Not Found"));
+    *response.status_mut() = StatusCode::NOT_FOUND;
+    response
+}
+
+fn hex_hash(hash: &[u8]) -> String {
+    hash.iter().map(|b| format!("{:02x}", b)).collect()
+}
@@ -0,0 +1,80 @@
+use serde::Deserialize;
+use warp::http::{HeaderValue, Response, StatusCode, header};
+use warp::hyper::body::Bytes;
+
+use crate::models::{ExtractedData, default_avatar_svg};
+
+#[derive(Deserialize)]
+pub(super) struct AvatarQuery {
+    encoding: Option<String>,
+}
+
+pub(super) fn serve(
+    data: Option<&ExtractedData>,
+    query: AvatarQuery,
+    cdn_avatars: bool,
+) -> Response<Bytes> {
+    let Some(user) = data.and_then(|d| d.user.as_ref()) else {
+        return status(StatusCode::NOT_FOUND, "No package loaded");
+    };
+
+    let Some(bytes) = &user.avatar else {
+        if cdn_avatars {
+            return redirect(&user.default_avatar_cdn_url());
+        }
+        return serve_default(user.default_avatar_index);
+    };
+
+    let (body, content_type) = match query.encoding.as_deref() {
+        None | Some("raw") => (
+            Bytes::from(bytes.clone()),
+            user.avatar_mime().unwrap_or("application/octet-stream"),
+        ),
+        Some("base64") => (
+            Bytes::from(user.avatar_base64().unwrap_or_default()),
+            "text/plain; charset=utf-8",
+        ),
+        Some("data-uri") => (
+            Bytes::from(user.avatar_data_uri().unwrap_or_default()),
+            "text/plain; charset=utf-8",
+        ),
+        Some(_) => {
+            return status(
+                StatusCode::BAD_REQUEST,
+                "encoding must be one of raw, base64, data-uri",
+            );
+        }
+    };
+
+    let mut response = Response::new(body);
+    let headers = response.headers_mut();
+    headers.insert(header::CONTENT_TYPE, HeaderValue::from_static(content_type));
+    headers.insert(header::CACHE_CONTROL, HeaderValue::from_static("no-cache"));
+    response
+}
+
+pub(super) fn serve_default(index: usize) -> Response<Bytes> {
+    let mut response = Response::new(Bytes::from_static(default_avatar_svg(index).as_bytes()));
+    let headers = response.headers_mut();
+    headers.insert(header::CONTENT_TYPE, HeaderValue::from_static("image/svg+xml"));
+    headers.insert(
+        header::CACHE_CONTROL,
+        HeaderValue::from_static("public, max-age=86400"),
+    );
+    response
+}
+
+fn redirect(url: &str) -> Response<Bytes> {
+    let mut response = Response::new(Bytes::new());
+    *response.status_mut() = StatusCode::FOUND;
+    if let Ok(value) = HeaderValue::from_str(url) {
+        response.headers_mut().insert(header::LOCATION, value);
+    }
+    response
+}
+
+fn status(code: StatusCode, message: &'static str) -> Response<Bytes> {
+    let mut response = Response::new(Bytes::from_static(message.as_bytes()));
+    *response.status_mut() = code;
+    response
+}
@@ -0,0 +1,233 @@
+#[cfg(all(feature = "ui", not(dpkv_no_ui)))]
+mod assets;
+mod avatar;
+
+use serde::Deserialize;
+use std::sync::{Arc, Mutex};
+use warp::Filter;
+use warp::http::StatusCode;
+
+use crate::cache::{CachedPackage, PackageCache};
+use crate::Parser;
+use crate::models::ExtractedData;
+use crate::redact::{self, RedactionOptions};
+
+#[derive(Debug, Clone, Default)]
+#[non_exhaustive]
+pub struct ServerOptions {
+    /// Redirect missing avatars to Discord's CDN instead of serving the bundled images.
+    pub cdn_avatars: bool,
+    /// Pseudonymize packages loaded from the cache. The preloaded package is expected to
+    /// be redacted by the caller already.
+    pub redaction: Option<RedactionOptions>,
+}
+
+impl ServerOptions {
+    pub fn cdn_avatars(mut self, cdn_avatars: bool) -> Self {
+        self.cdn_avatars = cdn_avatars;
+        self
+    }
+
+    pub fn redaction(mut self, redaction: Option<RedactionOptions>) -> Self {
+        self.redaction = redaction;
+        self
+    }
+}
+
+#[derive(Deserialize)]
+struct TrendQuery {
+    q: String,
+}
+
+/// Serves the API (and the embedded UI when bundled) on `127.0.0.1:3030`. Without a cache the
+/// package list endpoints answer with an error.
+pub async fn run(
+    cache: Option<PackageCache>,
+    extracted_data: Option<ExtractedData>,
+    options: ServerOptions,
+) {
+    let extracted_data = Arc::new(extracted_data);
+    let cache = Arc::new(Mutex::new(cache));
+
+    let avatar_data = extracted_data.clone();
+    let avatar = warp::path!("api" / "avatar")
+        .and(warp::get())
+        .and(warp::query::<avatar::AvatarQuery>())
+        .map(move |query| {
+            avatar::serve(avatar_data.as_ref().as_ref(), query, options.cdn_avatars)
+        });
+
+    let default_avatar = warp::path!("api" / "avatar" / "default" / usize)
+        .and(warp::get())
+        .map(avatar::serve_default);
+
+    let wrapped_data = extracted_data.clone();
+    let wrapped = warp::path!("api" / "wrapped")
+        .and(warp::get())
+        .map(move || {
+            let years: Vec<i32> = wrapped_data
+                .as_ref()
+                .iter()
+                .flat_map(|data| data.years.iter().map(|y| y.year))
+                .collect();
+            warp::reply::json(&years)
+        });
+
+    let year_data = extracted_data.clone();
+    let wrapped_year = warp::path!("api" / "wrapped" / i32)
+        .and(warp::get())
+        .map(move |year: i32| {
+            match year_data
+                .as_ref()
+                .iter()
+                .flat_map(|data| data.years.iter())
+                .find(|y| y.year == year)
+            {
+                Some(summary) => warp::reply::with_status(warp::reply::json(summary), StatusCode::OK),
+                None => warp::reply::with_status(
+                    warp::reply::json(&serde_json::json!({ "error": "No messages in that year" })),
+                    StatusCode::NOT_FOUND,
+                ),
+            }
+        });
+
+    let trend_data = extracted_data.clone();
+    let word_trend = warp::path!("api" / "words" / "trend")
+        .and(warp::get())
+        .and(warp::query::<TrendQuery>())
+        .map(move |query: TrendQuery| match trend_data.as_ref() {
+            Some(data) => warp::reply::with_status(
+                warp::reply::json(&Parser::word_trend(data, &query.q)),
+                StatusCode::OK,
+            ),
+            None => warp::reply::with_status(
+                warp::reply::json(&serde_json::json!({ "error": "No package loaded" })),
+                StatusCode::NOT_FOUND,
+            ),
+        });
+
+    let api = warp::path("api")
+        .and(warp::path("data"))
+        .and(warp::get())
+        .map(move || match extracted_data.as_ref() {
+            Some(data) => warp::reply::json(data),
+            None => warp::reply::json(&serde_json::json!({
+                "message": "No package loaded. Pass a package path to load one."
+            })),
+        });
+
+    let list_cache = cache.clone();
+    let list_redaction = options.redaction.clone();
+    let packages = warp::path!("api" / "packages")
+        .and(warp::get())
+        .map(move || match list_cache.lock().unwrap().as_ref().map(PackageCache::list) {
+            Some(Ok(packages)) => {
+                let packages: Vec<CachedPackage> = match &list_redaction {
+                    Some(redaction) => packages
+                        .iter()
+                        .map(|package| redact::redact_cached_package(package, redaction))
+                        .collect(),
+                    None => packages,
+                };
+                warp::reply::with_status(warp::reply::json(&packages), StatusCode::OK)
+            }
+            Some(Err(e)) => error_reply(e),
+            None => no_cache_reply(),
+        });
+
+    let load_cache = cache.clone();
+    let redaction = options.redaction.clone();
+    let package = warp::path!("api" / "packages" / String)
+        .and(warp::get())
+        .map(move |hash: String| {
+            let loaded = load_cache.lock().unwrap().as_ref().map(|c| c.load(&hash));
+            package_reply(loaded, redaction.as_ref())
+        });
+
+    #[cfg(all(feature = "ui", not(dpkv_no_ui)))]
+    let static_files = warp::get()
+        .and(warp::path::tail())
+        .and(warp::header::optional::<String>("if-none-match"))
+        .map(assets::serve);
+
+    #[cfg(any(not(feature = "ui"), dpkv_no_ui))]
+    let static_files = warp::get().and(warp::path::end()).map(|| {
+        eprintln!("[debug] Built without a UI bundle, serving the API only");
+        warp::reply::json(&serde_json::json!({
+            "message": "This build has no UI bundle. The API is available under /api."
+        }))
+    });
+
+    let routes = api
+        .or(avatar)
+        .or(default_avatar)
+        .or(wrapped)
+        .or(wrapped_year)
+        .or(word_trend)
+        .or(packages)
+        .or(package)
+        .or(static_files);
+
+    warp::serve(routes).run(([127, 0, 0, 1], 3030)).await;
+}
+
+fn error_reply(error: anyhow::Error) -> warp::reply::WithStatus<warp::reply::Json> {
+    eprintln!("[debug] Request failed: {}", error);
+    warp::reply::with_status(
+        warp::reply::json(&serde_json::json!({ "error": error.to_string() })),
+        StatusCode::INTERNAL_SERVER_ERROR,
+    )
+}
+
+fn package_reply(
+    loaded: Option<anyhow::Result<Option<ExtractedData>>>,
+    redaction: Option<&RedactionOptions>,
+) -> warp::reply::WithStatus<warp::reply::Json> {
+    match loaded {
+        None => no_cache_reply(),
+        Some(Ok(Some(data))) => {
+            let data = match redaction {
+                Some(redaction) => redact::redact(&data, redaction),
+                None => data,
+            };
+            warp::reply::with_status(warp::reply::json(&data), StatusCode::OK)
+        }
+        Some(Ok(None)) => warp::reply::with_status(
+            warp::reply::json(&serde_json::json!({ "error": "Package not found" })),
+            StatusCode::NOT_FOUND,
+        ),
+        Some(Err(e)) => error_reply(e),
+    }
+}
+
+fn no_cache_reply() -> warp::reply::WithStatus<warp::reply::Json> {
+    warp::reply::with_status(
+        warp::reply::json(&serde_json::json!({ "error": "The package cache is unavailable" })),
+        StatusCode::SERVICE_UNAVAILABLE,
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::fixtures::{data, guild_channel};
+    use warp::Reply;
+
+    fn status(reply: impl Reply) -> StatusCode {
+        reply.into_response().status()
+    }
+
+    #[test]
+    fn package_replies_follow_the_cache_state() {
+        let loaded = || Some(Ok(Some(data(vec![guild_channel("1", "general", &[])]))));
+        assert_eq!(status(package_reply(None, None)), StatusCode::SERVICE_UNAVAILABLE);
+        assert_eq!(status(package_reply(Some(Ok(None)), None)), StatusCode::NOT_FOUND);
+        assert_eq!(
+            status(package_reply(Some(Err(anyhow::anyhow!("broken"))), None)),
+            StatusCode::INTERNAL_SERVER_ERROR
+        );
+        assert_eq!(status(package_reply(loaded(), None)), StatusCode::OK);
+        let redaction = RedactionOptions::default();
+        assert_eq!(status(package_reply(loaded(), Some(&redaction))), StatusCode::OK);
+    }
+}
@@ -0,0 +1,90 @@
+//! Discord snowflake ids.
+//!
+//! Every user, channel, guild and message id encodes the millisecond it was created at in its
+//! top 42 bits, counted from the Discord epoch (the first second of 2015).
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::str::FromStr;
+
+/// 2015-01-01T00:00:00Z in milliseconds since the Unix epoch.
+pub const DISCORD_EPOCH_MS: u64 = 1_420_070_400_000;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct Snowflake(pub u64);
+
+impl Snowflake {
+    /// Wraps `id` unless it is too small to carry a timestamp, like the placeholder ids of
+    /// hand-made or very old exports.
+    pub fn new(id: u64) -> Option<Self> {
+        (id >> 22 != 0).then_some(Snowflake(id))
+    }
+
+    /// Parses a decimal id, returning `None` for anything that isn't a snowflake.
+    pub fn parse(id: &str) -> Option<Self> {
+        id.trim().parse().ok().and_then(Snowflake::new)
+    }
+
+    /// Milliseconds since [`DISCORD_EPOCH_MS`].
+    pub fn timestamp_ms(self) -> u64 {
+        self.0 >> 22
+    }
+
+    pub fn created_at(self) -> DateTime<Utc> {
+        DateTime::from_timestamp_millis((self.timestamp_ms() + DISCORD_EPOCH_MS) as i64)
+            .unwrap_or_default()
+    }
+}
+
+impl From<u64> for Snowflake {
+    fn from(id: u64) -> Self {
+        Snowflake(id)
+    }
+}
+
+impl FromStr for Snowflake {
+    type Err = std::num::ParseIntError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        s.trim().parse().map(Snowflake)
+    }
+}
+
+impl fmt::Display for Snowflake {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+/// When the object with this id was created, if `id` is a snowflake.
+pub fn created_at(id: &str) -> Option<DateTime<Utc>> {
+    Snowflake::parse(id).map(Snowflake::created_at)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn decodes_creation_time() {
+        // The example id from Discord's API reference.
+        let id = Snowflake::parse("175928847299117063").unwrap();
+        assert_eq!(id.timestamp_ms(), 41_944_705_796);
+        assert_eq!(
+            id.created_at().to_rfc3339(),
+            "2016-04-30T11:18:25.796+00:00"
+        );
+    }
+
+    #[test]
+    fn rejects_ids_without_timestamp() {
+        assert_eq!(Snowflake::new(0), None);
+        assert_eq!(Snowflake::new((1 << 22) - 1), None);
+        assert!(Snowflake::new(1 << 22).is_some());
+        assert_eq!(created_at("abc"), None);
+        assert_eq!(created_at("12"), None);
+        assert!(created_at(" 175928847299117063 ").is_some());
+    }
+}